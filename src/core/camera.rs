@@ -1,17 +1,104 @@
-use std::io::Write;
-
-use super::hittable::Hittable;
+use super::hittable::{HitRecord, Hittable};
+use super::hittable_list::HittableList;
 use super::ray::Ray;
-use crate::utils::color::{Color, write_color};
+use crate::utils::cancel::CancelToken;
+use crate::utils::color::Color;
+use crate::utils::framebuffer::Framebuffer;
 use crate::utils::interval::Interval;
+use crate::utils::sampler::{BoundedSampler, Sampler, SamplerKind};
+use crate::utils::tiles::{TileOrder, tiles};
+use crate::utils::timer::Timer;
+use crate::utils::tonemap::ToneMapping;
 use crate::utils::vec3::{Point3, Vec3};
 use rayon::prelude::*;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+// Sampler dimensions. The camera ray takes the first five; after that every bounce owns a
+// block of BOUNCE_DIMS, laid out by the offsets below, whether or not it uses all of them.
+// A ray takes one number per medium it crosses, however many that is, so media come last
+// and are held to MEDIA_DIMS per ray by a `BoundedSampler`.
+const PIXEL_DIM: u32 = 0;
+const LENS_DIM: u32 = 2;
+const TIME_DIM: u32 = 4;
+const FIRST_BOUNCE_DIM: u32 = 5;
+const LIGHT_DIM: u32 = 0; // light choice, point on it
+const BSDF_DIM: u32 = 3;
+const RR_DIM: u32 = 6;
+const MEDIA_DIMS: u32 = 4;
+const HIT_DIM: u32 = 7; // media along the ray
+const SHADOW_DIM: u32 = HIT_DIM + MEDIA_DIMS; // media along the shadow ray
+const BOUNCE_DIMS: u32 = SHADOW_DIM + MEDIA_DIMS;
+
+// How light samples and BSDF samples are weighted against each other when both can
+// find the same emitter (multiple importance sampling).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MisHeuristic {
+    Balance,
+    Power, // exponent 2
+}
+
+impl MisHeuristic {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "balance" => Some(MisHeuristic::Balance),
+            "power" => Some(MisHeuristic::Power),
+            _ => None,
+        }
+    }
+
+    // Weight of a sample taken with density `pdf` when `other_pdf` could also produce it.
+    fn weight(self, pdf: f64, other_pdf: f64) -> f64 {
+        let (a, b) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf),
+        };
+        if a + b > 0.0 { a / (a + b) } else { 0.0 }
+    }
+}
+
+// Where a render stands, handed to `Camera::progress` after every finished tile.
+pub struct RenderProgress {
+    pub pass: u32,         // counting from 1
+    pub passes: u32,       // at most, in this run; adaptive sampling may finish sooner
+    pub tiles_done: usize, // in this pass
+    pub tiles: usize,
+    pub rays: u64, // camera, bounce and shadow rays traced so far
+    pub elapsed: Duration,
+    pub time_left: Option<Duration>, // before the cancel deadline, if there is one
+}
+
+impl RenderProgress {
+    pub fn rays_per_second(&self) -> f64 {
+        self.rays as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+
+    // Extrapolated from the share of all passes' tiles done so far, or the time left if the
+    // deadline comes sooner.
+    pub fn eta(&self) -> Duration {
+        let total = self.passes as usize * self.tiles;
+        let done = (self.pass as usize - 1) * self.tiles + self.tiles_done;
+        if done == 0 {
+            return Duration::ZERO;
+        }
+        let eta = self.elapsed.mul_f64((total - done) as f64 / done as f64);
+        self.time_left.map_or(eta, |left| eta.min(left))
+    }
+}
+
+pub type ProgressFn = dyn Fn(&RenderProgress) + Sync;
+pub type CheckpointFn = dyn Fn(&RenderState) + Sync;
 
 pub struct Camera {
-    pub aspect_ratio: f64,      // width / height
-    pub image_width: u32,       // pixel width
-    pub samples_per_pixel: u32, // blue-noise samples per pixel
-    pub max_depth: u32,
+    pub aspect_ratio: f64,          // width / height
+    pub image_width: u32,           // pixel width
+    pub samples_per_pixel: u32,     // samples per pixel, the minimum when sampling adaptively
+    pub max_samples_per_pixel: u32, // adaptive budget; at or below samples_per_pixel disables it
+    pub pass_samples: u32,          // samples per pixel per progressive pass, 0 for a single pass
+    pub adaptive_threshold: f64,    // noise a pixel must get below to stop early, see render_pixel
+    pub max_depth: u32,             // hard limit on path length
+    pub rr_min_depth: u32,          // bounces before Russian roulette may end a path
     pub background: Color,
     pub vfov: f64,
     pub lookfrom: Point3,
@@ -19,10 +106,20 @@ pub struct Camera {
     pub vup: Vec3,
     pub defocus_angle: f64,
     pub focus_dist: f64,
+    pub tone_mapping: ToneMapping, // display transform for LDR outputs
+    pub mis_heuristic: MisHeuristic,
+    pub seed: u64, // same seed, same image
+    pub sampler: SamplerKind,
+    pub tile_size: u32, // edge of the square tiles work is handed out in, in pixels
+    pub tile_order: TileOrder,
+    pub progress: Option<Box<ProgressFn>>,
+    pub checkpoint: Option<Box<CheckpointFn>>, // called every checkpoint_interval and at the end
+    pub checkpoint_interval: Duration,
+    pub resume_from: Option<RenderState>, // taken by the next render, which adds to it
+    pub cancel: CancelToken,              // checked between tiles and passes
 
     // derived
     image_height: u32,
-    pixel_samples_scale: f64,
     center: Point3,
     pixel00_loc: Point3,
     pixel_delta_u: Vec3,
@@ -40,7 +137,11 @@ impl Default for Camera {
             aspect_ratio: 1.0,
             image_width: 100,
             samples_per_pixel: 10,
+            max_samples_per_pixel: 0,
+            pass_samples: 0,
+            adaptive_threshold: 0.02,
             max_depth: 10,
+            rr_min_depth: 3,
             vfov: 90.0,
             background: Color::default(),
             lookfrom: Point3::default(),
@@ -48,9 +149,19 @@ impl Default for Camera {
             vup: Vec3::new(0.0, 1.0, 0.0),
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            tone_mapping: ToneMapping::default(),
+            mis_heuristic: MisHeuristic::Power,
+            seed: 0,
+            sampler: SamplerKind::Sobol,
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+            progress: None,
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(60),
+            resume_from: None,
+            cancel: CancelToken::new(),
 
             image_height: 1,
-            pixel_samples_scale: 1.0,
             center: Point3::default(),
             pixel00_loc: Point3::default(),
             pixel_delta_u: Vec3::default(),
@@ -94,34 +205,195 @@ impl Camera {
         }
     }
 
-    pub fn render<W: Write>(&mut self, mut out: W, world: &dyn Hittable) -> std::io::Result<()> {
+    // Renders the scene into a linear HDR framebuffer; saving and display encoding are left
+    // to the caller. `lights` lists the emitters to sample directly; it may be empty, but
+    // every emitter missing from it is only found by chance bounces.
+    //
+    // Work goes in passes of `pass_samples` samples per pixel over the whole frame; the image
+    // so far is handed to `on_pass` after every pass but the last, which is returned. Sample
+    // indices carry on from pass to pass, so the final image does not depend on the pass size.
+    // Once `cancel` fires, tiles are only visited to give pixels still without a sample their
+    // first, so none comes back black, and the image comes back with every pixel averaged over
+    // the samples it did get.
+    pub fn render(
+        &mut self,
+        world: &dyn Hittable,
+        lights: &HittableList,
+        mut on_pass: impl FnMut(&Framebuffer),
+    ) -> Framebuffer {
         self.initialize();
 
-        writeln!(out, "P3\n{} {}\n255", self.image_width, self.image_height)?;
-
-        let rows: Vec<Vec<u8>> = (0..self.image_height as usize)
-            .into_par_iter()
-            .map(|jj| {
-                let j = jj as u32;
-                let mut row_bytes = Vec::with_capacity((self.image_width as usize) * 12);
-                for i in 0..self.image_width {
-                    let mut pixel_color = Color::default();
-                    for s in 0..self.samples_per_pixel {
-                        let r = self.get_ray(i, j, s);
-                        pixel_color += &self.ray_color(&r, self.max_depth, world);
-                    }
-                    let scaled = self.pixel_samples_scale * pixel_color;
-                    let _ = write_color(&mut row_bytes, &scaled);
-                }
-                row_bytes
-            })
+        let max_samples = self.max_samples_per_pixel.max(self.samples_per_pixel);
+        let pass_samples = if self.pass_samples == 0 {
+            max_samples
+        } else {
+            self.pass_samples
+        };
+
+        let tiles = tiles(
+            self.image_width,
+            self.image_height,
+            self.tile_size,
+            self.tile_order,
+        );
+        let mut state = self
+            .resume_from
+            .take()
+            .unwrap_or_else(|| RenderState::new(self.image_width, self.image_height));
+        assert!(
+            state.width == self.image_width && state.height == self.image_height,
+            "cannot resume a {}x{} render at {}x{}",
+            state.width,
+            state.height,
+            self.image_width,
+            self.image_height
+        );
+        // a resumed render only has the passes left over the samples it already holds, so
+        // progress and ETA measure the work still to do
+        let resumed = state.pixels.iter().map(|stats| stats.n).min().unwrap_or(0);
+        let passes = max_samples
+            .saturating_sub(resumed)
+            .div_ceil(pass_samples)
+            .max(1);
+        // the sample counts may have been raised since; let every pixel check again
+        for stats in &mut state.pixels {
+            stats.done = false;
+        }
+        // one lock per tile, so a checkpoint can copy finished tiles while others render
+        let buffers: Vec<Mutex<Vec<PixelStats>>> = tiles
+            .iter()
+            .map(|tile| Mutex::new(tile.pixels().map(|(i, j)| state.get(i, j)).collect()))
             .collect();
+        let gather = |state: &mut RenderState| {
+            for (tile, buffer) in tiles.iter().zip(&buffers) {
+                let buffer = buffer.lock().unwrap();
+                for ((i, j), stats) in tile.pixels().zip(buffer.iter()) {
+                    state.set(i, j, *stats);
+                }
+            }
+        };
+
+        let mut timer = Timer::new();
+        timer.start();
+        let rays = AtomicU64::new(0);
+        let mut since_checkpoint = Timer::new();
+        since_checkpoint.start();
+        let since_checkpoint = Mutex::new(since_checkpoint);
 
-        for row in rows {
-            out.write_all(&row)?;
+        let mut pass = 0;
+        loop {
+            pass += 1;
+            let tiles_done = AtomicUsize::new(0);
+            // each worker takes the next tile off a shared index into the ordered list, so
+            // tiles start in the scheduling order whatever rayon does with its workers
+            let next_tile = AtomicUsize::new(0);
+            (0..rayon::current_num_threads())
+                .into_par_iter()
+                .for_each(|_| {
+                    loop {
+                        let k = next_tile.fetch_add(1, Ordering::Relaxed);
+                        if k >= tiles.len() {
+                            return;
+                        }
+                        // once cancelled, a tile only gets the first samples it is missing
+                        let cancelled = self.cancel.is_cancelled();
+                        let (buffer, tile) = (&buffers[k], &tiles[k]);
+                        let mut sampler = self.sampler.create(self.seed, self.samples_per_pixel);
+                        let mut tile_rays = 0;
+                        let mut buffer = buffer.lock().unwrap();
+                        for ((i, j), stats) in tile.pixels().zip(buffer.iter_mut()) {
+                            let samples = if cancelled {
+                                u32::from(stats.n == 0)
+                            } else {
+                                pass_samples
+                            };
+                            if samples > 0 && !stats.done {
+                                tile_rays += self.render_pixel(
+                                    (i, j),
+                                    stats,
+                                    samples,
+                                    world,
+                                    lights,
+                                    sampler.as_mut(),
+                                );
+                            }
+                        }
+                        drop(buffer);
+
+                        let rays = rays.fetch_add(tile_rays, Ordering::Relaxed) + tile_rays;
+                        let tiles_done = tiles_done.fetch_add(1, Ordering::Relaxed) + 1;
+                        if let Some(progress) = &self.progress {
+                            progress(&RenderProgress {
+                                pass,
+                                passes,
+                                tiles_done,
+                                tiles: tiles.len(),
+                                rays,
+                                elapsed: timer.elapsed(),
+                                time_left: self.cancel.time_left(),
+                            });
+                        }
+                        if let Some(checkpoint) = &self.checkpoint {
+                            // held while writing, so only one checkpoint is written at a time
+                            let mut since = since_checkpoint.lock().unwrap();
+                            if since.elapsed() >= self.checkpoint_interval {
+                                let mut snapshot =
+                                    RenderState::new(self.image_width, self.image_height);
+                                gather(&mut snapshot);
+                                checkpoint(&snapshot);
+                                since.start();
+                            }
+                        }
+                    }
+                });
+
+            gather(&mut state);
+            let fb = state.to_framebuffer();
+            if self.cancel.is_cancelled() || state.pixels.iter().all(|stats| stats.done) {
+                // finished or stopped, it can be resumed later, with more samples if need be
+                if let Some(checkpoint) = &self.checkpoint {
+                    checkpoint(&state);
+                }
+                return fb;
+            }
+            on_pass(&fb);
         }
+    }
 
-        Ok(())
+    // Adds up to `pass_samples` samples to pixel (i, j). Past `samples_per_pixel`, sampling
+    // goes on while the standard error of the mean luminance is above `adaptive_threshold`
+    // times the square root of the mean, up to `max_samples_per_pixel`. The square root
+    // roughly follows how noise shows once the image is gamma encoded, so bright pixels are
+    // not held to the same absolute error as dark ones. Returns the number of rays traced.
+    fn render_pixel(
+        &self,
+        (i, j): (u32, u32),
+        stats: &mut PixelStats,
+        pass_samples: u32,
+        world: &dyn Hittable,
+        lights: &HittableList,
+        sampler: &mut dyn Sampler,
+    ) -> u64 {
+        let mut rays = 0;
+        let max_samples = self.max_samples_per_pixel.max(self.samples_per_pixel);
+        let end = stats.n.saturating_add(pass_samples).min(max_samples);
+        while stats.n < end {
+            if stats.n >= self.samples_per_pixel.max(2) {
+                let n = stats.n as f64;
+                let std_error = (stats.m2 / (n - 1.0) / n).sqrt();
+                // the floor keeps black pixels from chasing noise nobody can see
+                if std_error <= self.adaptive_threshold * stats.mean.max(1e-3).sqrt() {
+                    stats.done = true;
+                    return rays;
+                }
+            }
+            sampler.start_pixel_sample(i, j, stats.n);
+            let r = self.get_ray(i, j, sampler);
+            let c = self.ray_color(r, world, lights, sampler, &mut rays);
+            stats.add(&c);
+        }
+        stats.done = stats.n >= max_samples;
+        rays
     }
 
     fn initialize(&mut self) {
@@ -131,7 +403,6 @@ impl Camera {
             h = 1;
         }
         self.image_height = h as u32;
-        self.pixel_samples_scale = 1.0 / self.samples_per_pixel as f64;
 
         self.center = self.lookfrom;
 
@@ -164,67 +435,300 @@ impl Camera {
         self.defocus_disk_v = defocus_radius * self.v;
     }
 
-    fn ray_color(&self, r: &Ray, depth: u32, world: &dyn Hittable) -> Color {
-        if depth <= 0 {
-            return Color::default();
-        }
+    // Iterative path tracer. `throughput` is the product of all sample weights so far;
+    // past `rr_min_depth` bounces paths survive with a probability proportional to it and
+    // are reweighted, so ending them early adds noise but no bias.
+    fn ray_color(
+        &self,
+        r: Ray,
+        world: &dyn Hittable,
+        lights: &HittableList,
+        sampler: &mut dyn Sampler,
+        rays: &mut u64,
+    ) -> Color {
+        let mut color = Color::default();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = r;
+        // density the previous bounce picked `ray` with; `None` for the camera ray and delta
+        // bounces, whose emission no light sample could have found
+        let mut bsdf_pdf: Option<f64> = None;
+
+        for depth in 0..self.max_depth {
+            let dim = FIRST_BOUNCE_DIM + depth * BOUNCE_DIMS;
+            sampler.set_dimension(dim + HIT_DIM);
+            *rays += 1;
+            let hit = world.hit(
+                &ray,
+                &Interval::new(0.001, f64::INFINITY),
+                &mut BoundedSampler::new(sampler, MEDIA_DIMS),
+            );
+            let Some(rec) = hit else {
+                color += &(throughput * self.background);
+                break;
+            };
 
-        if let Some(rec) = world.hit(r, &Interval::new(0.001, f64::INFINITY)) {
-            let emitted = rec.material.emitted(rec.uv.0, rec.uv.1, &rec.p);
-            match rec.material.scatter(r, &rec) {
-                Some((attenuation, scattered)) => {
-                    return emitted + attenuation * self.ray_color(&scattered, depth - 1, world);
+            let mut emitted = rec.material.emitted(rec.uv.0, rec.uv.1, &rec.p);
+            if let Some(pdf) = bsdf_pdf
+                && emitted.length_squared() > 0.0
+            {
+                let light_pdf = lights.pdf_value(&ray.orig, &ray.dir, sampler);
+                emitted = self.mis_heuristic.weight(pdf, light_pdf) * emitted;
+            }
+            color += &(throughput * emitted);
+
+            sampler.set_dimension(dim + BSDF_DIM);
+            let Some(srec) = rec.material.sample(&ray, &rec, sampler) else {
+                break;
+            };
+            if srec.is_delta {
+                bsdf_pdf = None;
+            } else {
+                sampler.set_dimension(dim + LIGHT_DIM);
+                if let Some(light) = lights.sample_surface(&rec.p, sampler) {
+                    sampler.set_dimension(dim + SHADOW_DIM);
+                    color +=
+                        &(throughput * self.light_sample(&ray, &rec, light, world, sampler, rays));
+                }
+                bsdf_pdf = Some(srec.pdf);
+            }
+            throughput = throughput * srec.attenuation;
+            ray = srec.scattered;
+
+            if depth + 1 >= self.rr_min_depth {
+                let survive = throughput
+                    .x()
+                    .max(throughput.y())
+                    .max(throughput.z())
+                    .min(0.95);
+                sampler.set_dimension(dim + RR_DIM);
+                if sampler.get_1d() >= survive {
+                    break;
                 }
-                None => return emitted,
+                throughput /= survive;
             }
         }
 
-        return self.background;
+        color
     }
 
-    fn get_ray(&self, i: u32, j: u32, sample_idx: u32) -> Ray {
-        let offset = self.sample_square_blue(i, j, sample_idx);
+    // Next event estimation: one shadow ray towards point `p` sampled on a light with density
+    // `light_pdf`, MIS-weighted against the chance that the BSDF sample finds the same point.
+    fn light_sample(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        (p, light_pdf): (Point3, f64),
+        world: &dyn Hittable,
+        sampler: &mut dyn Sampler,
+        rays: &mut u64,
+    ) -> Color {
+        let shadow_ray = Ray::new(rec.p, p - rec.p, r.ts);
+        let f = rec.material.eval(r, rec, &shadow_ray);
+        if f.length_squared() == 0.0 {
+            return Color::default();
+        }
+
+        // whatever the shadow ray hits first is what the light sample sees
+        *rays += 1;
+        let hit = world.hit(
+            &shadow_ray,
+            &Interval::new(0.001, f64::INFINITY),
+            &mut BoundedSampler::new(sampler, MEDIA_DIMS),
+        );
+        let Some(light_rec) = hit else {
+            return Color::default();
+        };
+        let radiance = light_rec
+            .material
+            .emitted(light_rec.uv.0, light_rec.uv.1, &light_rec.p);
+        let bsdf_pdf = rec.material.pdf(r, rec, &shadow_ray);
+        self.mis_heuristic.weight(light_pdf, bsdf_pdf) / light_pdf * f * radiance
+    }
+
+    fn get_ray(&self, i: u32, j: u32, sampler: &mut dyn Sampler) -> Ray {
+        sampler.set_dimension(PIXEL_DIM);
+        let (dx, dy) = sampler.get_2d();
         let pixel_sample = self.pixel00_loc
-            + ((i as f64 + offset.0) * self.pixel_delta_u)
-            + ((j as f64 + offset.1) * self.pixel_delta_v);
+            + ((i as f64 + dx - 0.5) * self.pixel_delta_u)
+            + ((j as f64 + dy - 0.5) * self.pixel_delta_v);
         let ray_origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
-            self.defocus_disk_sample()
+            sampler.set_dimension(LENS_DIM);
+            self.defocus_disk_sample(sampler)
         };
         let ray_direction = pixel_sample - ray_origin;
-        let ray_ts = rand::random::<f64>();
+        sampler.set_dimension(TIME_DIM);
+        let ray_ts = sampler.get_1d();
         Ray::new(ray_origin, ray_direction, ray_ts)
     }
 
-    fn defocus_disk_sample(&self) -> Point3 {
+    fn defocus_disk_sample(&self, sampler: &mut dyn Sampler) -> Point3 {
         // return a sample point on the defocus disk
-        let p = Vec3::random_in_unit_disk();
+        let p = Vec3::random_in_unit_disk(sampler);
         p.x() * self.defocus_disk_u + p.y() * self.defocus_disk_v + self.center
     }
+}
+
+// Everything rendered so far, enough to carry on where a render stopped.
+#[derive(Clone)]
+pub struct RenderState {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<PixelStats>, // row-major like Framebuffer
+}
+
+impl RenderState {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![PixelStats::default(); width as usize * height as usize],
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> PixelStats {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    fn set(&mut self, x: u32, y: u32, stats: PixelStats) {
+        self.pixels[y as usize * self.width as usize + x as usize] = stats;
+    }
+
+    // Mean of the samples taken per pixel.
+    pub fn to_framebuffer(&self) -> Framebuffer {
+        let mut fb = Framebuffer::new(self.width, self.height);
+        for (k, stats) in self.pixels.iter().enumerate() {
+            fb.pixels[k] = stats.sum / stats.n.max(1) as f64;
+            fb.samples[k] = stats.n;
+        }
+        fb
+    }
+}
 
-    // Returns a blue-noise-like 2D offset in [-0.5, 0.5)^2 using a small tiled mask.
-    fn sample_square_blue(&self, i: u32, j: u32, sample_idx: u32) -> (f64, f64) {
-        const BN8X8_A: [u8; 64] = [
-            32, 0, 48, 16, 56, 24, 40, 8, 12, 44, 28, 60, 4, 36, 20, 52, 51, 19, 35, 3, 43, 11, 59,
-            27, 23, 55, 7, 39, 31, 63, 15, 47, 34, 2, 50, 18, 58, 26, 42, 10, 6, 38, 22, 54, 14,
-            46, 30, 62, 49, 17, 33, 1, 41, 9, 57, 25, 21, 53, 5, 37, 13, 45, 29, 61,
-        ];
-        const BN8X8_B: [u8; 64] = [
-            0, 32, 16, 48, 8, 40, 24, 56, 44, 12, 60, 28, 36, 4, 52, 20, 19, 51, 3, 35, 11, 43, 27,
-            59, 55, 23, 39, 7, 63, 31, 47, 15, 2, 34, 18, 50, 26, 58, 10, 42, 38, 6, 54, 22, 46,
-            14, 62, 30, 17, 49, 1, 33, 9, 41, 25, 57, 53, 21, 37, 5, 45, 13, 61, 29,
-        ];
-        let ix = (i & 7) as usize;
-        let jy = (j & 7) as usize;
-        let base = jy * 8 + ix;
-        let idx_x = ((base as u32 + sample_idx.wrapping_mul(73)) & 63) as usize;
-        let idx_y = ((base as u32 + sample_idx.wrapping_mul(97)) & 63) as usize;
-        let vx = BN8X8_A[idx_x] as f64;
-        let vy = BN8X8_B[idx_y] as f64;
-        // Map 0..63 to [-0.5, 0.5)
-        let dx = (vx + 0.5) / 64.0 - 0.5;
-        let dy = (vy + 0.5) / 64.0 - 0.5;
-        (dx, dy)
+// Running sums for one pixel across passes. `n` is also where the pixel's sample indices,
+// and with them its sampler sequence, pick up again.
+#[derive(Clone, Copy, Default)]
+pub struct PixelStats {
+    pub sum: Color,
+    // Welford's running mean and squared deviations of the luminance
+    pub mean: f64,
+    pub m2: f64,
+    pub n: u32,
+    done: bool,
+}
+
+impl PixelStats {
+    fn add(&mut self, c: &Color) {
+        self.sum += c;
+        self.n += 1;
+        let y = luminance(c);
+        let delta = y - self.mean;
+        self.mean += delta / self.n as f64;
+        self.m2 += delta * (y - self.mean);
+    }
+}
+
+fn luminance(c: &Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::constant_medium::ConstantMedium;
+    use crate::core::material::{DiffuseLight, Lambertian};
+    use crate::core::quad::{Quad, make_box};
+    use crate::utils::sampler::IndependentSampler;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    // Passes numbers through and notes every dimension a camera sample draws from.
+    struct Recorder {
+        inner: IndependentSampler,
+        dim: u32,
+        drawn: Vec<u32>,
+    }
+
+    impl Sampler for Recorder {
+        fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+            self.inner.start_pixel_sample(x, y, index);
+            self.dim = 0;
+            self.drawn.clear();
+        }
+
+        fn set_dimension(&mut self, dim: u32) {
+            self.dim = dim;
+        }
+
+        fn get_1d(&mut self) -> f64 {
+            self.drawn.push(self.dim);
+            self.dim += 1;
+            self.inner.get_1d()
+        }
+
+        fn get_2d(&mut self) -> (f64, f64) {
+            self.drawn.extend([self.dim, self.dim + 1]);
+            self.dim += 2;
+            self.inner.get_2d()
+        }
+    }
+
+    #[test]
+    fn media_do_not_share_dimensions_with_other_decisions() {
+        let white = Arc::new(Lambertian::new_color(Color::new(0.7, 0.7, 0.7)));
+        let mut world = HittableList::new();
+        world.add(Arc::new(Quad::new(
+            Point3::new(-5.0, 0.0, -5.0),
+            Vec3::new(0.0, 0.0, 10.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            white.clone(),
+        )));
+        // five thin slabs of fog between the floor and the light, too sparse to scatter in,
+        // so every ray up or down crosses all of them
+        for k in 0..5 {
+            let y = 1.0 + k as f64;
+            let slab = make_box(
+                Point3::new(-4.0, y, -4.0),
+                Point3::new(4.0, y + 0.5, 4.0),
+                white.clone(),
+            );
+            world.add(Arc::new(ConstantMedium::new_color(
+                slab,
+                1e-12,
+                Color::new(1.0, 1.0, 1.0),
+            )));
+        }
+        let light: Arc<dyn Hittable> = Arc::new(Quad::new(
+            Point3::new(-1.0, 8.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            Arc::new(DiffuseLight::new_color(Color::new(4.0, 4.0, 4.0))),
+        ));
+        world.add(light.clone());
+        let mut lights = HittableList::new();
+        lights.add(light);
+
+        let cam = Camera {
+            max_depth: 4,
+            ..Camera::default()
+        };
+        let mut sampler = Recorder {
+            inner: IndependentSampler::new(1),
+            dim: 0,
+            drawn: Vec::new(),
+        };
+        for index in 0..64 {
+            sampler.start_pixel_sample(0, 0, index);
+            sampler.set_dimension(FIRST_BOUNCE_DIM);
+            let ray = Ray::new(Point3::new(0.2, 7.5, 0.3), Vec3::new(0.0, -1.0, 0.0), 0.0);
+            let mut rays = 0;
+            cam.ray_color(ray, &world, &lights, &mut sampler, &mut rays);
+
+            let mut seen = HashSet::new();
+            for dim in &sampler.drawn {
+                assert!(seen.insert(*dim), "dimension {dim} drawn twice: {:?}", sampler.drawn);
+            }
+        }
     }
 }
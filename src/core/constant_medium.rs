@@ -7,6 +7,7 @@ use crate::core::texture::{SolidColor, Texture};
 use crate::utils::aabb::Aabb;
 use crate::utils::color::Color;
 use crate::utils::interval::Interval;
+use crate::utils::sampler::Sampler;
 use crate::utils::vec3::Vec3;
 
 pub struct ConstantMedium {
@@ -30,19 +31,20 @@ impl ConstantMedium {
 }
 
 impl Hittable for ConstantMedium {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         // Find the entry and exit points with the boundary
-        let mut rec1 = match self.boundary.hit(r, &Interval::universe()) {
-            Some(rec) => rec,
-            None => return None,
-        };
-        let mut rec2 = match self
-            .boundary
-            .hit(r, &Interval::new(rec1.t + 0.0001, f64::INFINITY))
-        {
+        let mut rec1 = match self.boundary.hit(r, &Interval::universe(), sampler) {
             Some(rec) => rec,
             None => return None,
         };
+        let mut rec2 =
+            match self
+                .boundary
+                .hit(r, &Interval::new(rec1.t + 0.0001, f64::INFINITY), sampler)
+            {
+                Some(rec) => rec,
+                None => return None,
+            };
 
         if rec1.t < ray_t.min {
             rec1.t = ray_t.min;
@@ -59,7 +61,7 @@ impl Hittable for ConstantMedium {
 
         let ray_length = r.dir.length();
         let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let hit_distance = self.neg_inv_density * (rand::random::<f64>()).ln();
+        let hit_distance = self.neg_inv_density * sampler.get_1d().ln();
 
         if hit_distance > distance_inside_boundary {
             return None;
@@ -70,7 +72,8 @@ impl Hittable for ConstantMedium {
 
         let mut rec = HitRecord::new(p, t, (0.0, 0.0), self.phase_function.clone());
         // Arbitrary normal and front_face for volume events
-        rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        let n = Vec3::new(1.0, 0.0, 0.0);
+        (rec.normal, rec.geometric_normal, rec.shading_normal) = (n, n, n);
         rec.front_face = true;
         Some(rec)
     }
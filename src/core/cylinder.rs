@@ -0,0 +1,222 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::hittable::{HitRecord, Hittable};
+use super::material::Material;
+use super::ray::Ray;
+use crate::utils::aabb::Aabb;
+use crate::utils::interval::Interval;
+use crate::utils::sampler::Sampler;
+use crate::utils::vec3::{Point3, Vec3};
+
+// Both shapes stand on `base` and run up the +y axis to a positive `height`; tilt them with
+// the instances in `hittable`. The sides are mapped like `Sphere`: u goes around the axis,
+// v goes from bottom (0) to top (1). Caps are mapped in polar form like `Disk`: u around
+// the axis, v from the center (0) out to the rim (1).
+
+// Finite cylinder, open or closed at both ends.
+pub struct Cylinder {
+    shape: Revolved,
+    pub material: Arc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point3,
+        radius: f64,
+        height: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let radius = radius.max(0.0);
+        Self {
+            shape: Revolved::new(base, radius, radius, height, capped),
+            material,
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        self.shape.hit(r, ray_t, &self.material)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.shape.aabb
+    }
+}
+
+// Cone from `radius` at the base to `top_radius` at `height`: a top radius of zero gives a
+// full cone with its apex at the top, anything else a truncated one.
+pub struct Cone {
+    shape: Revolved,
+    pub material: Arc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point3,
+        radius: f64,
+        top_radius: f64,
+        height: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            shape: Revolved::new(base, radius.max(0.0), top_radius.max(0.0), height, capped),
+            material,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        self.shape.hit(r, ray_t, &self.material)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.shape.aabb
+    }
+}
+
+// Surface whose radius changes linearly with height, x^2 + z^2 = (r0 + k y)^2 for
+// 0 <= y <= height, with optional flat caps; k = 0 is a cylinder.
+struct Revolved {
+    base: Point3,
+    r0: f64,
+    r1: f64,
+    height: f64,
+    capped: bool,
+    aabb: Aabb,
+}
+
+// Where a ray meets a `Revolved`, in its local frame.
+struct Crossing {
+    t: f64,
+    p: Point3,
+    outward_normal: Vec3,
+    uv: (f64, f64),
+    dpdu: Vec3,
+    dpdv: Vec3,
+}
+
+impl Revolved {
+    fn new(base: Point3, r0: f64, r1: f64, height: f64, capped: bool) -> Self {
+        // the slope and the side's v both divide by the height
+        assert!(height > 0.0, "height must be positive, got {height}");
+        let r = r0.max(r1);
+        let aabb = Aabb::from_points(base - Vec3::new(r, 0.0, r), base + Vec3::new(r, height, r));
+        Self {
+            base,
+            r0,
+            r1,
+            height,
+            capped,
+            aabb,
+        }
+    }
+
+    fn hit(&self, r: &Ray, ray_t: &Interval, material: &Arc<dyn Material>) -> Option<HitRecord> {
+        let o = r.orig - self.base;
+        let mut best: Option<Crossing> = None;
+        let mut closest = *ray_t;
+        for c in self
+            .side_crossings(&o, &r.dir, &closest)
+            .into_iter()
+            .chain(self.cap_crossings(&o, &r.dir, &closest))
+            .flatten()
+        {
+            if closest.surrounds(c.t) {
+                closest.max = c.t;
+                best = Some(c);
+            }
+        }
+
+        let c = best?;
+        let mut rec = HitRecord::new(c.p + self.base, c.t, c.uv, material.clone());
+        rec.set_face_normal(r, &c.outward_normal);
+        (rec.dpdu, rec.dpdv) = (c.dpdu, c.dpdv);
+        Some(rec)
+    }
+
+    fn side_crossings(&self, o: &Point3, d: &Vec3, ray_t: &Interval) -> [Option<Crossing>; 2] {
+        let k = (self.r1 - self.r0) / self.height;
+        let ro = self.r0 + k * o.y();
+        let a = d.x() * d.x() + d.z() * d.z() - k * k * d.y() * d.y();
+        let h = -(o.x() * d.x() + o.z() * d.z() - k * d.y() * ro);
+        let c = o.x() * o.x() + o.z() * o.z() - ro * ro;
+
+        // with a near zero the ray runs parallel to a cone line and the quadratic degrades
+        // to a linear equation with the single root c / 2h
+        let roots = if a.abs() < 1e-12 * (d.length_squared() * (1.0 + k * k)) {
+            if h == 0.0 {
+                return [None, None];
+            }
+            [Some(c / (2.0 * h)), None]
+        } else {
+            let discriminant = h * h - a * c;
+            if discriminant < 0.0 {
+                return [None, None];
+            }
+            let sqrtd = discriminant.sqrt();
+            [Some((h - sqrtd) / a), Some((h + sqrtd) / a)]
+        };
+
+        roots.map(|t| {
+            let t = t.filter(|&t| ray_t.surrounds(t))?;
+            let p = o + t * d;
+            if !(0.0..=self.height).contains(&p.y()) {
+                return None;
+            }
+            let rho = (p.x() * p.x() + p.z() * p.z()).sqrt().max(1e-12);
+            let outward_normal = Vec3::new(p.x(), -k * (self.r0 + k * p.y()), p.z()).unit_vector();
+            Some(Crossing {
+                t,
+                p,
+                outward_normal,
+                uv: (azimuth(&p), p.y() / self.height),
+                dpdu: 2.0 * PI * Vec3::new(p.z(), 0.0, -p.x()),
+                dpdv: Vec3::new(
+                    (self.r1 - self.r0) * p.x() / rho,
+                    self.height,
+                    (self.r1 - self.r0) * p.z() / rho,
+                ),
+            })
+        })
+    }
+
+    fn cap_crossings(&self, o: &Point3, d: &Vec3, ray_t: &Interval) -> [Option<Crossing>; 2] {
+        if !self.capped || d.y() == 0.0 {
+            return [None, None];
+        }
+        [(0.0, self.r0, -1.0), (self.height, self.r1, 1.0)].map(|(y, radius, side)| {
+            let t = (y - o.y()) / d.y();
+            if radius <= 0.0 || !ray_t.surrounds(t) {
+                return None;
+            }
+            let p = o + t * d;
+            let rho = (p.x() * p.x() + p.z() * p.z()).sqrt();
+            if rho > radius {
+                return None;
+            }
+            let radial = if rho > 0.0 {
+                Vec3::new(p.x() / rho, 0.0, p.z() / rho)
+            } else {
+                Vec3::new(1.0, 0.0, 0.0)
+            };
+            Some(Crossing {
+                t,
+                p: Point3::new(p.x(), y, p.z()),
+                outward_normal: Vec3::new(0.0, side, 0.0),
+                uv: (azimuth(&p), rho / radius),
+                dpdu: 2.0 * PI * Vec3::new(p.z(), 0.0, -p.x()),
+                dpdv: radius * radial,
+            })
+        })
+    }
+}
+
+// Fraction of a turn around the y axis, with the seam where `Sphere` has it.
+fn azimuth(p: &Point3) -> f64 {
+    ((-p.z()).atan2(p.x()) + PI) / (2.0 * PI)
+}
@@ -0,0 +1,127 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::hittable::{HitRecord, Hittable};
+use super::material::Material;
+use super::ray::Ray;
+use crate::utils::aabb::Aabb;
+use crate::utils::interval::Interval;
+use crate::utils::onb::Onb;
+use crate::utils::sampler::Sampler;
+use crate::utils::vec3::{Point3, Vec3};
+
+// Flat disk facing `normal`, with a hole of `inner_radius` (0 for none). Mapped in polar
+// form like the caps of a `Cylinder`: u goes around the center, v from the inner edge (0),
+// or the center without a hole, out to the rim (1).
+pub struct Disk {
+    pub center: Point3,
+    pub radius: f64,
+    pub inner_radius: f64,
+    pub material: Arc<dyn Material>,
+    pub aabb: Aabb,
+    pub frame: Onb, // w is the normal
+    pub area: f64,
+}
+
+impl Disk {
+    pub fn new(
+        center: Point3,
+        normal: Vec3,
+        radius: f64,
+        inner_radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let radius = radius.max(0.0);
+        let inner_radius = inner_radius.clamp(0.0, radius);
+        let frame = Onb::new(&normal);
+        // the disk spans at most its radius along each axis, scaled by how far the axis
+        // leans out of the normal
+        let w = frame.w;
+        let extent = Vec3::new(
+            radius * (1.0 - w.x() * w.x()).max(0.0).sqrt(),
+            radius * (1.0 - w.y() * w.y()).max(0.0).sqrt(),
+            radius * (1.0 - w.z() * w.z()).max(0.0).sqrt(),
+        );
+        Self {
+            center,
+            radius,
+            inner_radius,
+            material,
+            aabb: Aabb::from_points(center - extent, center + extent),
+            frame,
+            area: PI * (radius * radius - inner_radius * inner_radius),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let normal = &self.frame.w;
+        let denom = normal.dot(&r.dir);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = normal.dot(&(self.center - r.orig)) / denom;
+        if !ray_t.contains(t) {
+            return None;
+        }
+
+        let p = r.at(t);
+        let offset = p - self.center;
+        let (x, y) = (offset.dot(&self.frame.u), offset.dot(&self.frame.v));
+        let rho = (x * x + y * y).sqrt();
+        if rho > self.radius || rho < self.inner_radius {
+            return None;
+        }
+
+        let phi = y.atan2(x).rem_euclid(2.0 * PI);
+        let span = self.radius - self.inner_radius;
+        let uv = (
+            phi / (2.0 * PI),
+            if span > 0.0 {
+                (rho - self.inner_radius) / span
+            } else {
+                1.0
+            },
+        );
+        let mut rec = HitRecord::new(p, t, uv, self.material.clone());
+        rec.set_face_normal(r, normal);
+        let radial = if rho > 0.0 {
+            (x / rho) * self.frame.u + (y / rho) * self.frame.v
+        } else {
+            self.frame.u
+        };
+        rec.dpdu = 2.0 * PI * (-y * self.frame.u + x * self.frame.v);
+        rec.dpdv = span * radial;
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+
+    // Uniform over the area: the square root keeps equal areas for equal sample steps.
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let (a, b) = sampler.get_2d();
+        let inner2 = self.inner_radius * self.inner_radius;
+        let rho = (inner2 + a * (self.radius * self.radius - inner2)).sqrt();
+        let phi = 2.0 * PI * b;
+        let p = self.center + rho * phi.cos() * self.frame.u + rho * phi.sin() * self.frame.v;
+        let pdf = self.pdf_value(origin, &(p - origin), sampler);
+        (pdf > 0.0).then_some((p, pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        let r = Ray::new(*origin, *direction, 0.0);
+        let Some(rec) = self.hit(&r, &Interval::new(0.001, f64::INFINITY), sampler) else {
+            return 0.0;
+        };
+        // uniform area density converted to solid angle: dist^2 / (|cos| * area)
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(&self.frame.w) / direction.length()).abs();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area)
+    }
+}
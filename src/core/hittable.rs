@@ -4,11 +4,23 @@ use super::material::Material;
 use super::ray::Ray;
 use crate::utils::aabb::Aabb;
 use crate::utils::interval::Interval;
+use crate::utils::mat4::Mat4;
+use crate::utils::onb::Onb;
+use crate::utils::quat::Quat;
+use crate::utils::sampler::Sampler;
 use crate::utils::vec3::{Point3, Vec3};
 
+// Normals are unit length. `geometric_normal` and `shading_normal` point out of the
+// surface, on the same side; `normal` is the shading normal turned against the ray, which
+// is what materials use. `dpdu` and `dpdv` are the surface derivatives along the texture
+// coordinates, not normalized.
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
+    pub geometric_normal: Vec3,
+    pub shading_normal: Vec3,
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
     pub t: f64,
     pub uv: (f64, f64),
     pub front_face: bool,
@@ -20,6 +32,10 @@ impl HitRecord {
         Self {
             p,
             normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            shading_normal: Vec3::default(),
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
             t,
             uv,
             front_face: false,
@@ -27,19 +43,77 @@ impl HitRecord {
         }
     }
 
+    // Sets both normals to `outward_normal`.
     pub fn set_face_normal(&mut self, r: &Ray, outward_normal: &Vec3) {
-        self.front_face = r.dir.dot(outward_normal) < 0.0;
+        self.geometric_normal = *outward_normal;
+        self.shading_normal = *outward_normal;
+        self.orient(r);
+    }
+
+    // Replaces the shading normal, flipped if need be onto the geometric normal's side.
+    pub fn set_shading_normal(&mut self, r: &Ray, n: &Vec3) {
+        let n = n.unit_vector();
+        self.shading_normal = if n.dot(&self.geometric_normal) < 0.0 {
+            -n
+        } else {
+            n
+        };
+        self.orient(r);
+    }
+
+    // Moves the hit out of an instance: `point` maps positions, `vector` maps directions,
+    // and `normal` maps normals (the inverse transpose of `vector`). `front_face` is
+    // recomputed against `r`, the ray in the outer space.
+    pub fn transform(
+        &mut self,
+        r: &Ray,
+        point: impl Fn(&Point3) -> Point3,
+        vector: impl Fn(&Vec3) -> Vec3,
+        normal: impl Fn(&Vec3) -> Vec3,
+    ) {
+        self.p = point(&self.p);
+        self.dpdu = vector(&self.dpdu);
+        self.dpdv = vector(&self.dpdv);
+        self.geometric_normal = normal(&self.geometric_normal).unit_vector();
+        self.shading_normal = normal(&self.shading_normal).unit_vector();
+        self.orient(r);
+    }
+
+    // Orthonormal frame around the shading normal with `u` along `dpdu` where it has one,
+    // for materials that care about direction on the surface.
+    pub fn shading_frame(&self) -> Onb {
+        Onb::from_tangent(&self.normal, &self.dpdu)
+    }
+
+    fn orient(&mut self, r: &Ray) {
+        self.front_face = r.dir.dot(&self.geometric_normal) < 0.0;
         self.normal = if self.front_face {
-            *outward_normal
+            self.shading_normal
         } else {
-            -*outward_normal
+            -self.shading_normal
         };
     }
 }
 
 pub trait Hittable: Send + Sync {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord>;
     fn bounding_box(&self) -> Aabb;
+
+    // Light sampling: a random point on the surface as seen from `origin`, together with the
+    // density of its direction per unit solid angle. Shapes that cannot be sampled return
+    // `None` and must not be put in a light list.
+    fn sample_surface(
+        &self,
+        _origin: &Point3,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Point3, f64)> {
+        None
+    }
+
+    // Solid angle density with which `sample_surface` from `origin` picks `direction`.
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3, _sampler: &mut dyn Sampler) -> f64 {
+        0.0
+    }
 }
 
 pub struct Translate {
@@ -56,10 +130,10 @@ impl Translate {
 }
 
 impl Hittable for Translate {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         let offset_r = Ray::new(r.orig - self.offset, r.dir, r.ts);
-        if let Some(mut rec) = self.obj.hit(&offset_r, ray_t) {
-            rec.p = rec.p + self.offset;
+        if let Some(mut rec) = self.obj.hit(&offset_r, ray_t, sampler) {
+            rec.transform(r, |p| p + self.offset, |v| *v, |n| *n);
             Some(rec)
         } else {
             None
@@ -69,6 +143,16 @@ impl Hittable for Translate {
     fn bounding_box(&self) -> Aabb {
         self.aabb
     }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let (p, pdf) = self.obj.sample_surface(&(origin - self.offset), sampler)?;
+        Some((p + self.offset, pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        self.obj
+            .pdf_value(&(origin - self.offset), direction, sampler)
+    }
 }
 
 pub struct RotateY {
@@ -83,29 +167,13 @@ impl RotateY {
         let radians = angle_degrees.to_radians();
         let sin_theta = radians.sin();
         let cos_theta = radians.cos();
-        let bbox = obj.bounding_box();
-
-        let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
-        let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
-
-        for i in 0..2 {
-            let x = if i == 1 { bbox.x.max } else { bbox.x.min };
-            for j in 0..2 {
-                let y = if j == 1 { bbox.y.max } else { bbox.y.min };
-                for k in 0..2 {
-                    let z = if k == 1 { bbox.z.max } else { bbox.z.min };
-                    let newx = cos_theta * x + sin_theta * z;
-                    let newz = -sin_theta * x + cos_theta * z;
-                    let tester = Point3::new(newx, y, newz);
-                    for c in 0..3 {
-                        min[c] = min[c].min(tester[c]);
-                        max[c] = max[c].max(tester[c]);
-                    }
-                }
-            }
-        }
-
-        let aabb = Aabb::from_points(min, max);
+        let aabb = map_aabb(&obj.bounding_box(), |p| {
+            Point3::new(
+                cos_theta * p.x() + sin_theta * p.z(),
+                p.y(),
+                -sin_theta * p.x() + cos_theta * p.z(),
+            )
+        });
         Self {
             obj,
             sin_theta,
@@ -113,37 +181,34 @@ impl RotateY {
             aabb,
         }
     }
+
+    // Inverse rotation, world space to object space.
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x() - self.sin_theta * v.z(),
+            v.y(),
+            self.sin_theta * v.x() + self.cos_theta * v.z(),
+        )
+    }
+
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x() + self.sin_theta * v.z(),
+            v.y(),
+            -self.sin_theta * v.x() + self.cos_theta * v.z(),
+        )
+    }
 }
 
 impl Hittable for RotateY {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         // Rotate ray into object space (inverse rotation)
-        let origin = Point3::new(
-            self.cos_theta * r.orig.x() - self.sin_theta * r.orig.z(),
-            r.orig.y(),
-            self.sin_theta * r.orig.x() + self.cos_theta * r.orig.z(),
-        );
-        let direction = Vec3::new(
-            self.cos_theta * r.dir.x() - self.sin_theta * r.dir.z(),
-            r.dir.y(),
-            self.sin_theta * r.dir.x() + self.cos_theta * r.dir.z(),
-        );
-        let rotated_r = Ray::new(origin, direction, r.ts);
+        let rotated_r = Ray::new(self.to_object(&r.orig), self.to_object(&r.dir), r.ts);
 
-        if let Some(mut rec0) = self.obj.hit(&rotated_r, ray_t) {
+        if let Some(mut rec0) = self.obj.hit(&rotated_r, ray_t, sampler) {
             // Rotate intersection back to world space
-            let p = Point3::new(
-                self.cos_theta * rec0.p.x() + self.sin_theta * rec0.p.z(),
-                rec0.p.y(),
-                -self.sin_theta * rec0.p.x() + self.cos_theta * rec0.p.z(),
-            );
-            let normal = Vec3::new(
-                self.cos_theta * rec0.normal.x() + self.sin_theta * rec0.normal.z(),
-                rec0.normal.y(),
-                -self.sin_theta * rec0.normal.x() + self.cos_theta * rec0.normal.z(),
-            );
-            rec0.p = p;
-            rec0.normal = normal;
+            let to_world = |v: &Vec3| self.to_world(v);
+            rec0.transform(r, to_world, to_world, to_world);
             Some(rec0)
         } else {
             None
@@ -153,4 +218,536 @@ impl Hittable for RotateY {
     fn bounding_box(&self) -> Aabb {
         self.aabb
     }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let (p, pdf) = self.obj.sample_surface(&self.to_object(origin), sampler)?;
+        Some((self.to_world(&p), pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        self.obj
+            .pdf_value(&self.to_object(origin), &self.to_object(direction), sampler)
+    }
+}
+
+pub struct RotateX {
+    pub obj: Arc<dyn Hittable>,
+    pub sin_theta: f64,
+    pub cos_theta: f64,
+    pub aabb: Aabb,
+}
+
+impl RotateX {
+    pub fn new(obj: Arc<dyn Hittable>, angle_degrees: f64) -> Self {
+        let (sin_theta, cos_theta) = angle_degrees.to_radians().sin_cos();
+        let aabb = map_aabb(&obj.bounding_box(), |p| {
+            Point3::new(
+                p.x(),
+                cos_theta * p.y() - sin_theta * p.z(),
+                sin_theta * p.y() + cos_theta * p.z(),
+            )
+        });
+        Self {
+            obj,
+            sin_theta,
+            cos_theta,
+            aabb,
+        }
+    }
+
+    // Inverse rotation, world space to object space.
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            v.x(),
+            self.cos_theta * v.y() + self.sin_theta * v.z(),
+            -self.sin_theta * v.y() + self.cos_theta * v.z(),
+        )
+    }
+
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            v.x(),
+            self.cos_theta * v.y() - self.sin_theta * v.z(),
+            self.sin_theta * v.y() + self.cos_theta * v.z(),
+        )
+    }
+}
+
+impl Hittable for RotateX {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let rotated_r = Ray::new(self.to_object(&r.orig), self.to_object(&r.dir), r.ts);
+        let mut rec = self.obj.hit(&rotated_r, ray_t, sampler)?;
+        let to_world = |v: &Vec3| self.to_world(v);
+        rec.transform(r, to_world, to_world, to_world);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let (p, pdf) = self.obj.sample_surface(&self.to_object(origin), sampler)?;
+        Some((self.to_world(&p), pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        self.obj
+            .pdf_value(&self.to_object(origin), &self.to_object(direction), sampler)
+    }
+}
+
+pub struct RotateZ {
+    pub obj: Arc<dyn Hittable>,
+    pub sin_theta: f64,
+    pub cos_theta: f64,
+    pub aabb: Aabb,
+}
+
+impl RotateZ {
+    pub fn new(obj: Arc<dyn Hittable>, angle_degrees: f64) -> Self {
+        let (sin_theta, cos_theta) = angle_degrees.to_radians().sin_cos();
+        let aabb = map_aabb(&obj.bounding_box(), |p| {
+            Point3::new(
+                cos_theta * p.x() - sin_theta * p.y(),
+                sin_theta * p.x() + cos_theta * p.y(),
+                p.z(),
+            )
+        });
+        Self {
+            obj,
+            sin_theta,
+            cos_theta,
+            aabb,
+        }
+    }
+
+    // Inverse rotation, world space to object space.
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x() + self.sin_theta * v.y(),
+            -self.sin_theta * v.x() + self.cos_theta * v.y(),
+            v.z(),
+        )
+    }
+
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x() - self.sin_theta * v.y(),
+            self.sin_theta * v.x() + self.cos_theta * v.y(),
+            v.z(),
+        )
+    }
+}
+
+impl Hittable for RotateZ {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let rotated_r = Ray::new(self.to_object(&r.orig), self.to_object(&r.dir), r.ts);
+        let mut rec = self.obj.hit(&rotated_r, ray_t, sampler)?;
+        let to_world = |v: &Vec3| self.to_world(v);
+        rec.transform(r, to_world, to_world, to_world);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let (p, pdf) = self.obj.sample_surface(&self.to_object(origin), sampler)?;
+        Some((self.to_world(&p), pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        self.obj
+            .pdf_value(&self.to_object(origin), &self.to_object(direction), sampler)
+    }
+}
+
+// Per-axis scale about the origin; a negative factor mirrors. A sphere scaled unevenly
+// becomes an ellipsoid.
+pub struct Scale {
+    pub obj: Arc<dyn Hittable>,
+    pub factors: Vec3,
+    pub aabb: Aabb,
+}
+
+impl Scale {
+    // Panics if a factor is zero.
+    pub fn new(obj: Arc<dyn Hittable>, factors: Vec3) -> Self {
+        assert!(
+            (0..3).all(|c| factors[c] != 0.0),
+            "scale factors must be nonzero"
+        );
+        let aabb = map_aabb(&obj.bounding_box(), |p| p * factors);
+        Self { obj, factors, aabb }
+    }
+
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            v.x() / self.factors.x(),
+            v.y() / self.factors.y(),
+            v.z() / self.factors.z(),
+        )
+    }
+
+    // Solid angle densities scale by |det S^-1| / |S^-1 w|^3 for a unit world direction w.
+    fn pdf_to_world(&self, object_pdf: f64, world_dir: &Vec3) -> f64 {
+        let f = &self.factors;
+        let d = self.to_object(&world_dir.unit_vector());
+        object_pdf / (f.x() * f.y() * f.z()).abs() / d.length().powi(3)
+    }
+}
+
+impl Hittable for Scale {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        // the direction is not renormalized, so t means the same in both spaces
+        let scaled_r = Ray::new(self.to_object(&r.orig), self.to_object(&r.dir), r.ts);
+        let mut rec = self.obj.hit(&scaled_r, ray_t, sampler)?;
+        // the inverse transpose of a diagonal matrix is its inverse
+        rec.transform(
+            r,
+            |p| p * self.factors,
+            |v| v * self.factors,
+            |n| self.to_object(n),
+        );
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let (p, pdf) = self.obj.sample_surface(&self.to_object(origin), sampler)?;
+        let p = p * self.factors;
+        Some((p, self.pdf_to_world(pdf, &(p - origin))))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        let pdf = self
+            .obj
+            .pdf_value(&self.to_object(origin), &self.to_object(direction), sampler);
+        self.pdf_to_world(pdf, direction)
+    }
+}
+
+// Box around the eight mapped corners of `bbox`; for a linear `f` it holds the mapped box.
+fn map_aabb(bbox: &Aabb, f: impl Fn(Point3) -> Point3) -> Aabb {
+    let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+    let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for x in [bbox.x.min, bbox.x.max] {
+        for y in [bbox.y.min, bbox.y.max] {
+            for z in [bbox.z.min, bbox.z.max] {
+                let p = f(Point3::new(x, y, z));
+                for c in 0..3 {
+                    min[c] = min[c].min(p[c]);
+                    max[c] = max[c].max(p[c]);
+                }
+            }
+        }
+    }
+    Aabb::from_points(min, max)
+}
+
+// Any affine instance: scale, shear, rotation about any axis, or several composed into one
+// matrix. Wraps and can be wrapped by the other instances.
+pub struct Transform {
+    pub obj: Arc<dyn Hittable>,
+    pub matrix: Mat4,  // object space to world space
+    pub inverse: Mat4, // world space to object space
+    pub aabb: Aabb,
+}
+
+impl Transform {
+    // Panics unless `matrix` is affine and invertible.
+    pub fn new(obj: Arc<dyn Hittable>, matrix: Mat4) -> Self {
+        assert!(matrix.is_affine(), "transform matrix must be affine");
+        let inverse = matrix
+            .inverse()
+            .expect("transform matrix must be invertible");
+        let aabb = matrix.transform_aabb(&obj.bounding_box());
+        Self {
+            obj,
+            matrix,
+            inverse,
+            aabb,
+        }
+    }
+
+    // Solid angle densities scale by the Jacobian of the direction mapping, which for a
+    // linear map L taking world directions w to object directions is |det L| / |L w|^3.
+    fn pdf_to_world(&self, object_pdf: f64, world_dir: &Vec3) -> f64 {
+        let d = self.inverse.transform_vector(&world_dir.unit_vector());
+        object_pdf * self.inverse.linear_determinant().abs() / d.length().powi(3)
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        hit_transformed(&*self.obj, &self.matrix, &self.inverse, r, ray_t, sampler)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let object_origin = self.inverse.transform_point(origin);
+        let (p, pdf) = self.obj.sample_surface(&object_origin, sampler)?;
+        let p = self.matrix.transform_point(&p);
+        Some((p, self.pdf_to_world(pdf, &(p - origin))))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        let object_origin = self.inverse.transform_point(origin);
+        let object_dir = self.inverse.transform_vector(direction);
+        let pdf = self.obj.pdf_value(&object_origin, &object_dir, sampler);
+        self.pdf_to_world(pdf, direction)
+    }
+}
+
+fn hit_transformed(
+    obj: &dyn Hittable,
+    matrix: &Mat4,
+    inverse: &Mat4,
+    r: &Ray,
+    ray_t: &Interval,
+    sampler: &mut dyn Sampler,
+) -> Option<HitRecord> {
+    // the direction is not renormalized, so t means the same in both spaces
+    let object_r = Ray::new(
+        inverse.transform_point(&r.orig),
+        inverse.transform_vector(&r.dir),
+        r.ts,
+    );
+    let mut rec = obj.hit(&object_r, ray_t, sampler)?;
+    rec.transform(
+        r,
+        |p| matrix.transform_point(p),
+        |v| matrix.transform_vector(v),
+        |n| inverse.transform_normal(n),
+    );
+    Some(rec)
+}
+
+// Pose of an `AnimatedTransform` at `time`: scaled, then rotated, then moved.
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f64,
+    pub offset: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, offset: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            time,
+            offset,
+            rotation,
+            scale,
+        }
+    }
+
+    fn lerp(&self, other: &Keyframe, t: f64) -> Keyframe {
+        Keyframe {
+            time: self.time + t * (other.time - self.time),
+            offset: self.offset + t * (other.offset - self.offset),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale + t * (other.scale - self.scale),
+        }
+    }
+
+    fn matrix(&self) -> Mat4 {
+        Mat4::translation(&self.offset) * self.rotation.to_mat4() * Mat4::scaling(&self.scale)
+    }
+
+    fn inverse(&self) -> Mat4 {
+        let s = &self.scale;
+        Mat4::scaling(&Vec3::new(1.0 / s.x(), 1.0 / s.y(), 1.0 / s.z()))
+            * self.rotation.conjugate().to_mat4()
+            * Mat4::translation(&-self.offset)
+    }
+}
+
+// Motion blur for any object: the pose is interpolated between keyframes at each ray's
+// time, linearly for offset and scale and by slerp for rotation, and held before the
+// first keyframe and after the last. Not usable as a light, since light sampling does not
+// know the time.
+pub struct AnimatedTransform {
+    pub obj: Arc<dyn Hittable>,
+    pub keys: Vec<Keyframe>, // sorted by time
+    pub aabb: Aabb,
+}
+
+impl AnimatedTransform {
+    // Steps per keyframe interval at which the bounding box is evaluated.
+    const BOUND_STEPS: usize = 32;
+
+    // Panics unless there is at least one keyframe and every scale is positive; mirror
+    // with a `Transform` inside instead.
+    pub fn new(obj: Arc<dyn Hittable>, mut keys: Vec<Keyframe>) -> Self {
+        assert!(!keys.is_empty(), "animated transform needs a keyframe");
+        assert!(
+            keys.iter().all(|k| (0..3).all(|c| k.scale[c] > 0.0)),
+            "animated transform scales must be positive"
+        );
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let bbox = obj.bounding_box();
+        let mut aabb = keys[0].matrix().transform_aabb(&bbox);
+        // Boxes at evenly spaced poses, each padded by how far any point of the object can
+        // move in half a step: no further than the offset change, plus the rotation angle
+        // times the largest scaled radius, plus the scale change times the largest radius.
+        let radius = Vec3::new(
+            bbox.x.min.abs().max(bbox.x.max.abs()),
+            bbox.y.min.abs().max(bbox.y.max.abs()),
+            bbox.z.min.abs().max(bbox.z.max.abs()),
+        );
+        for pair in keys.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            let max_scale = Vec3::new(
+                a.scale.x().max(b.scale.x()),
+                a.scale.y().max(b.scale.y()),
+                a.scale.z().max(b.scale.z()),
+            );
+            let travel = (b.offset - a.offset).length()
+                + a.rotation.angle_to(&b.rotation) * (max_scale * radius).length()
+                + ((b.scale - a.scale) * radius).length();
+            let pad = travel / Self::BOUND_STEPS as f64 / 2.0;
+            for step in 0..=Self::BOUND_STEPS {
+                let key = a.lerp(b, step as f64 / Self::BOUND_STEPS as f64);
+                let mut step_box = key.matrix().transform_aabb(&bbox);
+                step_box.x.expand(2.0 * pad);
+                step_box.y.expand(2.0 * pad);
+                step_box.z.expand(2.0 * pad);
+                aabb = aabb.merge(&step_box);
+            }
+        }
+
+        Self { obj, keys, aabb }
+    }
+
+    fn pose(&self, time: f64) -> Keyframe {
+        let next = self.keys.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return self.keys[0];
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1];
+        }
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        a.lerp(b, (time - a.time) / (b.time - a.time))
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let pose = self.pose(r.ts);
+        hit_transformed(
+            &*self.obj,
+            &pose.matrix(),
+            &pose.inverse(),
+            r,
+            ray_t,
+            sampler,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::material::Lambertian;
+    use crate::core::quad::Quad;
+    use crate::core::sphere::Sphere;
+    use crate::utils::color::Color;
+    use crate::utils::sampler::IndependentSampler;
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new_color(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    // Rotated 30 degrees about y, then moved.
+    fn nested(obj: Arc<dyn Hittable>, offset: Vec3) -> Translate {
+        Translate::new(Arc::new(RotateY::new(obj, 30.0)), offset)
+    }
+
+    fn hit(obj: &dyn Hittable, orig: Point3, dir: Vec3) -> HitRecord {
+        let r = Ray::new(orig, dir, 0.0);
+        obj.hit(
+            &r,
+            &Interval::new(0.001, f64::INFINITY),
+            &mut IndependentSampler::new(1),
+        )
+        .expect("ray should hit")
+    }
+
+    fn assert_close(a: f64, b: f64, what: &str) {
+        assert!((a - b).abs() < 1e-9, "{what}: {a} != {b}");
+    }
+
+    fn check(rec: &HitRecord, dir: &Vec3, front_face: bool, outward: &Vec3) {
+        let frame = rec.shading_frame();
+        for (axis, name) in [(&frame.u, "u"), (&frame.v, "v"), (&frame.w, "w")] {
+            assert_close(axis.length(), 1.0, name);
+        }
+        assert_close(frame.u.dot(&frame.v), 0.0, "u.v");
+        assert_close(frame.v.dot(&frame.w), 0.0, "v.w");
+        assert_close(frame.w.dot(&frame.u), 0.0, "w.u");
+        assert_close(frame.w.dot(&rec.normal), 1.0, "w.normal");
+
+        assert_close(
+            rec.shading_normal.dot(&rec.geometric_normal),
+            1.0,
+            "shading.geometric",
+        );
+        assert_close(rec.geometric_normal.dot(outward), 1.0, "geometric.outward");
+        assert_eq!(rec.front_face, front_face);
+        let side = if front_face { 1.0 } else { -1.0 };
+        assert_close(rec.normal.dot(&rec.shading_normal), side, "normal.shading");
+        assert!(rec.normal.dot(dir) < 0.0, "normal should face the ray");
+    }
+
+    #[test]
+    fn nested_instance_quad_from_both_sides() {
+        // u x v is +z, which the rotation turns to (sin 30, 0, cos 30)
+        let quad = Arc::new(Quad::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            material(),
+        ));
+        let obj = nested(quad, Vec3::new(5.0, 0.0, 0.0));
+        let (sin, cos) = 30f64.to_radians().sin_cos();
+        let outward = Vec3::new(sin, 0.0, cos);
+        let target = Point3::new(5.0 + 0.5 * cos, 0.5, -0.5 * sin);
+
+        let rec = hit(&obj, target + 2.0 * outward, -outward);
+        check(&rec, &-outward, true, &outward);
+        assert_close((rec.p - target).length(), 0.0, "hit point");
+
+        let rec = hit(&obj, target - 2.0 * outward, outward);
+        check(&rec, &outward, false, &outward);
+    }
+
+    #[test]
+    fn nested_instance_sphere_from_outside_and_inside() {
+        let sphere = Arc::new(Sphere::new(Point3::new(1.0, 0.0, 0.0), 1.0, material()));
+        let obj = nested(sphere, Vec3::new(0.0, 2.0, 0.0));
+        let (sin, cos) = 30f64.to_radians().sin_cos();
+        let center = Point3::new(cos, 2.0, -sin);
+
+        let dir = Vec3::new(-1.0, 0.0, -1.0);
+        let rec = hit(&obj, center - 3.0 * dir, dir);
+        check(&rec, &dir, true, &-dir.unit_vector());
+
+        let rec = hit(&obj, center, dir);
+        check(&rec, &dir, false, &dir.unit_vector());
+    }
 }
@@ -4,6 +4,8 @@ use super::hittable::{HitRecord, Hittable};
 use super::ray::Ray;
 use crate::utils::aabb::Aabb;
 use crate::utils::interval::Interval;
+use crate::utils::sampler::Sampler;
+use crate::utils::vec3::{Point3, Vec3};
 
 #[derive(Clone)]
 pub struct HittableList {
@@ -36,13 +38,13 @@ impl HittableList {
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         let mut hit_anything: Option<HitRecord> = None;
         let mut closest_so_far = ray_t.max;
         let mut temp_interval = Interval::new(ray_t.min, closest_so_far);
 
         for object in &self.objects {
-            if let Some(temp_rec) = object.hit(r, &temp_interval) {
+            if let Some(temp_rec) = object.hit(r, &temp_interval, sampler) {
                 closest_so_far = temp_rec.t;
                 temp_interval.max = closest_so_far;
                 hit_anything = Some(temp_rec);
@@ -55,4 +57,28 @@ impl Hittable for HittableList {
     fn bounding_box(&self) -> Aabb {
         self.aabb
     }
+
+    // Picks one object uniformly; the density covers every object that could have produced
+    // the direction, so overlapping lights stay unbiased.
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let i = sampler.index(self.objects.len());
+        let (p, _) = self.objects[i].sample_surface(origin, sampler)?;
+        let pdf = self.pdf_value(origin, &(p - origin), sampler);
+        (pdf > 0.0).then_some((p, pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .objects
+            .iter()
+            .map(|o| o.pdf_value(origin, direction, sampler))
+            .sum();
+        sum / self.objects.len() as f64
+    }
 }
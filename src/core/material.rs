@@ -1,16 +1,44 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 
 use super::hittable::HitRecord;
 use super::ray::Ray;
 use super::texture::{SolidColor, Texture};
 use crate::utils::color::Color;
+use crate::utils::sampler::Sampler;
 use crate::utils::vec3::{Point3, Vec3};
 
+// Outcome of sampling a material. `attenuation` is the sample weight f * cos / pdf; for
+// delta lobes (perfect mirrors, glass) `pdf` is meaningless and left at zero.
+pub struct ScatterRecord {
+    pub attenuation: Color,
+    pub scattered: Ray,
+    pub pdf: f64,
+    pub is_delta: bool,
+}
+
 pub trait Material: Send + Sync {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+    // Picks an outgoing direction, or `None` if the path is absorbed.
+    fn sample(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
         None
     }
 
+    // BSDF times the cosine term for light leaving along `scattered`; black for delta
+    // materials, which no other strategy can sample.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> Color {
+        Color::default()
+    }
+
+    // Solid angle density with which `sample` would choose `scattered`.
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
     fn emitted(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
         Color::default()
     }
@@ -30,14 +58,32 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
-        let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
-        }
-        let scattered = Ray::new(rec.p, scatter_direction, r_in.ts);
-        let attenuation = self.tex.value(rec.uv.0, rec.uv.1, &rec.p);
-        Some((attenuation, scattered))
+    fn sample(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        let uvw = rec.shading_frame();
+        let direction = uvw.transform(&Vec3::random_cosine_direction(sampler));
+        let scattered = Ray::new(rec.p, direction, r_in.ts);
+        Some(ScatterRecord {
+            attenuation: self.tex.value(rec.uv.0, rec.uv.1, &rec.p),
+            pdf: self.pdf(r_in, rec, &scattered),
+            scattered,
+            is_delta: false,
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let albedo = self.tex.value(rec.uv.0, rec.uv.1, &rec.p);
+        self.pdf(r_in, rec, scattered) * albedo
+    }
+
+    // cosine weighted, which is also f * cos / albedo
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = rec.normal.dot(&scattered.dir.unit_vector());
+        cosine.max(0.0) / PI
     }
 }
 
@@ -56,12 +102,23 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+    fn sample(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
         let reflected = Vec3::reflect(&r_in.dir.unit_vector(), &rec.normal);
-        let reflected = reflected.unit_vector() + self.fuzz * Vec3::random_unit_vector();
+        let reflected = reflected.unit_vector() + self.fuzz * Vec3::random_unit_vector(sampler);
         let scattered = Ray::new(rec.p, reflected, r_in.ts);
         if scattered.dir.dot(&rec.normal) > 0.0 {
-            Some((self.albedo, scattered))
+            // fuzz perturbs a mirror rather than defining a lobe, so it stays a delta
+            Some(ScatterRecord {
+                attenuation: self.albedo,
+                scattered,
+                pdf: 0.0,
+                is_delta: true,
+            })
         } else {
             None
         }
@@ -86,7 +143,12 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+    fn sample(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
         let attenuation = Color::new(1.0, 1.0, 1.0);
         let ri = if rec.front_face {
             1.0 / self.refraction_index
@@ -99,13 +161,18 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
         let cannot_refract = ri * sin_theta > 1.0;
         let direction =
-            if cannot_refract || Dielectric::reflectance(cos_theta, ri) > rand::random::<f64>() {
+            if cannot_refract || Dielectric::reflectance(cos_theta, ri) > sampler.get_1d() {
                 Vec3::reflect(&unit_direction, &rec.normal)
             } else {
                 Vec3::refract(&unit_direction, &rec.normal, ri)
             };
         let scattered = Ray::new(rec.p, direction, r_in.ts);
-        Some((attenuation, scattered))
+        Some(ScatterRecord {
+            attenuation,
+            scattered,
+            pdf: 0.0,
+            is_delta: true,
+        })
     }
 }
 
@@ -124,10 +191,6 @@ impl DiffuseLight {
 }
 
 impl Material for DiffuseLight {
-    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<(Color, Ray)> {
-        None
-    }
-
     fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
         self.tex.value(u, v, p)
     }
@@ -147,9 +210,28 @@ impl Isotropic {
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
-        let scattered = Ray::new(rec.p, Vec3::random_unit_vector(), r_in.ts);
-        let attenuation = self.tex.value(rec.uv.0, rec.uv.1, &rec.p);
-        Some((attenuation, scattered))
+    fn sample(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        let scattered = Ray::new(rec.p, Vec3::random_unit_vector(sampler), r_in.ts);
+        Some(ScatterRecord {
+            attenuation: self.tex.value(rec.uv.0, rec.uv.1, &rec.p),
+            pdf: self.pdf(r_in, rec, &scattered),
+            scattered,
+            is_delta: false,
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        // uniform phase function, no cosine term inside a medium
+        let albedo = self.tex.value(rec.uv.0, rec.uv.1, &rec.p);
+        self.pdf(r_in, rec, scattered) * albedo
+    }
+
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
     }
 }
@@ -1,9 +1,14 @@
 pub(crate) mod camera;
 pub(crate) mod constant_medium;
+pub(crate) mod cylinder;
+pub(crate) mod disk;
 pub(crate) mod hittable;
 pub(crate) mod hittable_list;
 pub(crate) mod material;
 pub(crate) mod quad;
 pub(crate) mod ray;
+pub(crate) mod scene;
 pub(crate) mod sphere;
 pub(crate) mod texture;
+pub(crate) mod torus;
+pub(crate) mod triangle;
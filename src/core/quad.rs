@@ -1,3 +1,4 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 
 use super::hittable::{HitRecord, Hittable};
@@ -6,54 +7,185 @@ use super::ray::Ray;
 use crate::core::hittable_list::HittableList;
 use crate::utils::aabb::Aabb;
 use crate::utils::interval::Interval;
+use crate::utils::sampler::Sampler;
 use crate::utils::vec3::{Point3, Vec3};
 
-pub struct Quad {
+// Flat primitives on the plane through `p0` spanned by `u` and `v`. A hit at
+// p0 + alpha u + beta v is kept when the shape's interior test accepts (alpha, beta);
+// everything else (plane intersection, bounds, light sampling) is shared.
+pub trait PlanarShape: Send + Sync {
+    // Range of alpha and beta the shape occupies; texture coordinates map it onto [0, 1].
+    const DOMAIN: (f64, f64);
+
+    fn is_interior(&self, alpha: f64, beta: f64) -> bool;
+
+    // Area in (alpha, beta) units, so the shape's true area is this times |u x v|.
+    fn area(&self) -> f64;
+
+    // Uniformly distributed interior point for a point of the unit square.
+    fn sample(&self, a: f64, b: f64) -> (f64, f64);
+}
+
+// `p0` is a corner; the edges are `u` and `v`.
+pub struct Parallelogram;
+
+impl PlanarShape for Parallelogram {
+    const DOMAIN: (f64, f64) = (0.0, 1.0);
+
+    fn is_interior(&self, alpha: f64, beta: f64) -> bool {
+        let unit_interval = Interval::new(0.0, 1.0);
+        unit_interval.contains(alpha) && unit_interval.contains(beta)
+    }
+
+    fn area(&self) -> f64 {
+        1.0
+    }
+
+    fn sample(&self, a: f64, b: f64) -> (f64, f64) {
+        (a, b)
+    }
+}
+
+// `p0` is a corner; the other two are at `p0 + u` and `p0 + v`.
+pub struct TriangleShape;
+
+impl PlanarShape for TriangleShape {
+    const DOMAIN: (f64, f64) = (0.0, 1.0);
+
+    fn is_interior(&self, alpha: f64, beta: f64) -> bool {
+        alpha >= 0.0 && beta >= 0.0 && alpha + beta <= 1.0
+    }
+
+    fn area(&self) -> f64 {
+        0.5
+    }
+
+    // the square root spreads samples evenly between the wide and narrow ends
+    fn sample(&self, a: f64, b: f64) -> (f64, f64) {
+        let s = a.sqrt();
+        (1.0 - s, b * s)
+    }
+}
+
+// `p0` is the center; `u` and `v` are the semi-axes, so they give a circle when they are
+// perpendicular and of equal length.
+pub struct EllipseShape;
+
+impl PlanarShape for EllipseShape {
+    const DOMAIN: (f64, f64) = (-1.0, 1.0);
+
+    fn is_interior(&self, alpha: f64, beta: f64) -> bool {
+        alpha * alpha + beta * beta <= 1.0
+    }
+
+    fn area(&self) -> f64 {
+        PI
+    }
+
+    fn sample(&self, a: f64, b: f64) -> (f64, f64) {
+        AnnulusShape { inner: 0.0 }.sample(a, b)
+    }
+}
+
+// An ellipse with the same shape scaled by `inner` (between 0 and 1) cut out.
+pub struct AnnulusShape {
+    pub inner: f64,
+}
+
+impl PlanarShape for AnnulusShape {
+    const DOMAIN: (f64, f64) = (-1.0, 1.0);
+
+    fn is_interior(&self, alpha: f64, beta: f64) -> bool {
+        let r2 = alpha * alpha + beta * beta;
+        r2 <= 1.0 && r2 >= self.inner * self.inner
+    }
+
+    fn area(&self) -> f64 {
+        PI * (1.0 - self.inner * self.inner)
+    }
+
+    fn sample(&self, a: f64, b: f64) -> (f64, f64) {
+        let inner2 = self.inner * self.inner;
+        let r = (inner2 + a * (1.0 - inner2)).sqrt();
+        let (sin, cos) = (2.0 * PI * b).sin_cos();
+        (r * cos, r * sin)
+    }
+}
+
+pub type Quad = Planar<Parallelogram>;
+pub type PlanarTriangle = Planar<TriangleShape>;
+pub type Ellipse = Planar<EllipseShape>;
+pub type Annulus = Planar<AnnulusShape>;
+
+pub struct Planar<S: PlanarShape> {
     pub p0: Point3,
     pub u: Vec3,
     pub v: Vec3,
+    pub shape: S,
     pub material: Arc<dyn Material>,
     pub aabb: Aabb,
     pub normal: Vec3,
     pub d: f64,
+    pub area: f64,
 }
 
 impl Quad {
     pub fn new(p0: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
-        let normal = u.cross(&v).unit_vector();
+        Self::with_shape(p0, u, v, Parallelogram, material)
+    }
+}
+
+impl PlanarTriangle {
+    pub fn new(p0: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        Self::with_shape(p0, u, v, TriangleShape, material)
+    }
+}
+
+impl Ellipse {
+    pub fn new(center: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        Self::with_shape(center, u, v, EllipseShape, material)
+    }
+}
+
+impl Annulus {
+    pub fn new(center: Point3, u: Vec3, v: Vec3, inner: f64, material: Arc<dyn Material>) -> Self {
+        let inner = inner.clamp(0.0, 1.0);
+        Self::with_shape(center, u, v, AnnulusShape { inner }, material)
+    }
+}
+
+impl<S: PlanarShape> Planar<S> {
+    pub fn with_shape(p0: Point3, u: Vec3, v: Vec3, shape: S, material: Arc<dyn Material>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
         let d = normal.dot(&p0);
-        let mut quad = Self {
+        let area = n.length() * shape.area();
+        let mut planar = Self {
             p0,
             u,
             v,
+            shape,
             material,
             aabb: Aabb::default(),
             normal,
             d,
+            area,
         };
-        quad.set_bounding_box();
-        quad
+        planar.set_bounding_box();
+        planar
     }
 
     fn set_bounding_box(&mut self) {
-        let bbox_diag1 = Aabb::from_points(self.p0, self.p0 + self.u + self.v);
-        let bbox_diag2 = Aabb::from_points(self.p0 + self.v, self.p0 + self.u);
+        let (lo, hi) = S::DOMAIN;
+        let corner = |a: f64, b: f64| self.p0 + a * self.u + b * self.v;
+        let bbox_diag1 = Aabb::from_points(corner(lo, lo), corner(hi, hi));
+        let bbox_diag2 = Aabb::from_points(corner(lo, hi), corner(hi, lo));
         self.aabb = bbox_diag1.merge(&bbox_diag2);
     }
-
-    fn is_interior(&self, alpha: f64, beta: f64) -> bool {
-        let unit_interval = Interval::new(0.0, 1.0);
-
-        if !unit_interval.contains(alpha) || !unit_interval.contains(beta) {
-            return false;
-        }
-
-        return true;
-    }
 }
 
-impl Hittable for Quad {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+impl<S: PlanarShape> Hittable for Planar<S> {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
         let denom = self.normal.dot(&r.dir);
         if denom.abs() < 1e-8 {
             return None;
@@ -65,26 +197,50 @@ impl Hittable for Quad {
         }
 
         let intersection = r.at(t);
-        let mut hit_record = HitRecord::new(intersection, t, (0.0, 0.0), self.material.clone());
-
         let qp = intersection - self.p0;
         let alpha =
             (self.v.cross(&qp)).dot(&self.normal) / (self.v.cross(&self.u)).dot(&self.normal);
         let beta =
             (qp.cross(&self.u)).dot(&self.normal) / (self.v.cross(&self.u)).dot(&self.normal);
 
-        if !self.is_interior(alpha, beta) {
+        if !self.shape.is_interior(alpha, beta) {
             return None;
         }
 
-        hit_record.uv = (alpha, beta);
+        let (lo, hi) = S::DOMAIN;
+        let uv = ((alpha - lo) / (hi - lo), (beta - lo) / (hi - lo));
+        let mut hit_record = HitRecord::new(intersection, t, uv, self.material.clone());
         hit_record.set_face_normal(r, &self.normal);
+        hit_record.dpdu = (hi - lo) * self.u;
+        hit_record.dpdv = (hi - lo) * self.v;
         Some(hit_record)
     }
 
     fn bounding_box(&self) -> Aabb {
         self.aabb
     }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let (a, b) = sampler.get_2d();
+        let (alpha, beta) = self.shape.sample(a, b);
+        let p = self.p0 + alpha * self.u + beta * self.v;
+        let pdf = self.pdf_value(origin, &(p - origin), sampler);
+        (pdf > 0.0).then_some((p, pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        let r = Ray::new(*origin, *direction, 0.0);
+        let Some(rec) = self.hit(&r, &Interval::new(0.001, f64::INFINITY), sampler) else {
+            return 0.0;
+        };
+        // uniform area density converted to solid angle: dist^2 / (|cos| * area)
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(&self.normal) / direction.length()).abs();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area)
+    }
 }
 
 pub fn make_box(p0: Point3, p1: Point3, material: Arc<dyn Material>) -> Arc<dyn Hittable> {
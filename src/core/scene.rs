@@ -0,0 +1,899 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::camera::{Camera, MisHeuristic};
+use super::constant_medium::ConstantMedium;
+use super::cylinder::{Cone, Cylinder};
+use super::disk::Disk;
+use super::hittable::{
+    AnimatedTransform, Hittable, Keyframe, RotateX, RotateY, RotateZ, Scale, Transform, Translate,
+};
+use super::hittable_list::HittableList;
+use super::material::{Dielectric, DiffuseLight, Isotropic, Lambertian, Material, Metal};
+use super::quad::{Annulus, Ellipse, PlanarTriangle, Quad, make_box};
+use super::sphere::Sphere;
+use super::texture::{CheckerTexture, ImageTexture, NoiseTexture, SolidColor, Texture};
+use super::torus::Torus;
+use super::triangle::Triangle;
+use crate::utils::bvh::{BvhConfig, BvhNode};
+use crate::utils::json::{Json, JsonValue};
+use crate::utils::mat4::Mat4;
+use crate::utils::obj::load_obj;
+use crate::utils::quat::Quat;
+use crate::utils::sampler::SamplerKind;
+use crate::utils::tiles::TileOrder;
+use crate::utils::tonemap::ToneMap;
+use crate::utils::vec3::Vec3;
+
+// Declarative scene files (JSON, `//` comments allowed):
+//
+// {
+//   "output": "image.ppm",
+//   "bvh": true,
+//   "camera": { "image_width": 600, "lookfrom": [278, 278, -800], ... },
+//   "textures": { "checker": { "type": "checker", "scale": 0.32, "odd": [0.2, 0.3, 0.1], "even": [0.9, 0.9, 0.9] } },
+//   "materials": { "ground": { "type": "lambertian", "albedo": "checker" } },
+//   "shapes": { "tall_box": { "type": "box", "a": [0, 0, 0], "b": [165, 330, 165], "material": "white" } },
+//   "world": [ { "type": "translate", "offset": [265, 0, 295], "object": "tall_box" }, ... ],
+//   "lights": [ "ceiling_light" ]
+// }
+//
+// A `transform` object takes either a 4x4 affine `matrix` (rows, acting on column vectors)
+// or any of `scale`, `axis` with `angle` (degrees), and `offset`, applied in that order.
+//
+// An `animated_transform` blurs its object between `keyframes`, each with a `time` in the
+// camera's shutter interval [0, 1] and the same optional `scale` (positive), `axis` with
+// `angle`, and `offset`.
+//
+// `lights` lists the emitters to sample directly (quads, triangles, ellipses, annuli, disks,
+// spheres and boxes, or transforms of them); each should also appear in `world`.
+//
+// Wherever a texture, material or object is expected, a string refers to a named entry
+// defined earlier in the file, an object defines one inline, and for textures a
+// `[r, g, b]` array is shorthand for a solid color.
+
+pub struct Scene {
+    pub camera: Camera,
+    pub world: Arc<dyn Hittable>,
+    pub lights: HittableList,
+    pub output: Option<String>,
+}
+
+struct SceneError {
+    line: usize,
+    message: String,
+}
+
+type Result<T> = std::result::Result<T, SceneError>;
+
+fn error<T>(j: &Json, message: impl Into<String>) -> Result<T> {
+    Err(SceneError {
+        line: j.line,
+        message: message.into(),
+    })
+}
+
+pub fn load_scene(path: impl AsRef<Path>, bvh_config: &BvhConfig) -> io::Result<Scene> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+    let root = Json::parse(&source).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}:{e}", path.display()),
+        )
+    })?;
+
+    let mut loader = Loader {
+        dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        bvh_config: *bvh_config,
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        shapes: HashMap::new(),
+    };
+    loader.load(&root).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}:{}: {}", path.display(), e.line, e.message),
+        )
+    })
+}
+
+struct Loader {
+    dir: PathBuf,
+    bvh_config: BvhConfig,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    shapes: HashMap<String, Arc<dyn Hittable>>,
+}
+
+impl Loader {
+    fn load(&mut self, root: &Json) -> Result<Scene> {
+        check_fields(
+            root,
+            "scene",
+            &[
+                "output",
+                "bvh",
+                "camera",
+                "textures",
+                "materials",
+                "shapes",
+                "world",
+                "lights",
+            ],
+        )?;
+
+        if let Some(textures) = root.get("textures") {
+            for (name, j) in as_object(textures, "textures")? {
+                let tex = self.texture(j)?;
+                self.textures.insert(name.clone(), tex);
+            }
+        }
+        if let Some(materials) = root.get("materials") {
+            for (name, j) in as_object(materials, "materials")? {
+                let mat = self.material(j)?;
+                self.materials.insert(name.clone(), mat);
+            }
+        }
+        if let Some(shapes) = root.get("shapes") {
+            for (name, j) in as_object(shapes, "shapes")? {
+                let obj = self.object(j)?;
+                self.shapes.insert(name.clone(), obj);
+            }
+        }
+
+        let world_json = required(root, "world", "scene")?;
+        let mut world = HittableList::new();
+        for j in as_array(world_json, "world")? {
+            world.add(self.object(j)?);
+        }
+        if world.objects.is_empty() {
+            return error(world_json, "'world' must contain at least one object");
+        }
+
+        let mut lights = HittableList::new();
+        if let Some(lights_json) = root.get("lights") {
+            for j in as_array(lights_json, "lights")? {
+                lights.add(self.object(j)?);
+            }
+        }
+
+        let camera = match root.get("camera") {
+            Some(j) => camera(j)?,
+            None => Camera::default(),
+        };
+        let output = root
+            .get("output")
+            .map(|j| as_str(j, "output"))
+            .transpose()?;
+
+        let use_bvh = match root.get("bvh") {
+            Some(j) => as_bool(j, "bvh")?,
+            None => true,
+        };
+        let world: Arc<dyn Hittable> = if use_bvh {
+            Arc::new(BvhNode::new_from_list_with(&mut world, &self.bvh_config))
+        } else {
+            Arc::new(world)
+        };
+
+        Ok(Scene {
+            camera,
+            world,
+            lights,
+            output: output.map(str::to_string),
+        })
+    }
+
+    // Files are looked up next to the scene first, then through the usual search paths.
+    fn resolve(&self, file: &str) -> String {
+        let local = self.dir.join(file);
+        if local.exists() {
+            local.to_string_lossy().into_owned()
+        } else {
+            file.to_string()
+        }
+    }
+
+    fn texture(&self, j: &Json) -> Result<Arc<dyn Texture>> {
+        match &j.value {
+            JsonValue::String(name) => match self.textures.get(name) {
+                Some(t) => Ok(t.clone()),
+                None => error(j, format!("unknown texture '{name}'")),
+            },
+            JsonValue::Array(_) => Ok(Arc::new(SolidColor::new(vec3(j, "color")?))),
+            JsonValue::Object(_) => {
+                let kind = as_str(required(j, "type", "texture")?, "type")?;
+                match kind {
+                    "solid" => {
+                        check_fields(j, kind, &["type", "color"])?;
+                        Ok(Arc::new(SolidColor::new(vec3(
+                            required(j, "color", kind)?,
+                            "color",
+                        )?)))
+                    }
+                    "checker" => {
+                        check_fields(j, kind, &["type", "scale", "odd", "even"])?;
+                        Ok(Arc::new(CheckerTexture::new(
+                            number(required(j, "scale", kind)?, "scale")?,
+                            self.texture(required(j, "odd", kind)?)?,
+                            self.texture(required(j, "even", kind)?)?,
+                        )))
+                    }
+                    "image" => {
+                        check_fields(j, kind, &["type", "file"])?;
+                        let file = as_str(required(j, "file", kind)?, "file")?;
+                        Ok(Arc::new(ImageTexture::new(self.resolve(file))))
+                    }
+                    "noise" => {
+                        check_fields(j, kind, &["type", "scale", "seed"])?;
+                        let scale = number(required(j, "scale", kind)?, "scale")?;
+                        let seed = match j.get("seed") {
+                            Some(v) => u64::from(integer(v, "seed")?),
+                            None => 0,
+                        };
+                        Ok(Arc::new(NoiseTexture::with_seed(scale, seed)))
+                    }
+                    _ => error(
+                        j,
+                        format!(
+                            "unknown texture type '{kind}' (expected solid, checker, image or noise)"
+                        ),
+                    ),
+                }
+            }
+            _ => error(
+                j,
+                format!(
+                    "expected a texture name, color or texture object, found {}",
+                    j.type_name()
+                ),
+            ),
+        }
+    }
+
+    fn material(&self, j: &Json) -> Result<Arc<dyn Material>> {
+        if let JsonValue::String(name) = &j.value {
+            return match self.materials.get(name) {
+                Some(m) => Ok(m.clone()),
+                None => error(j, format!("unknown material '{name}'")),
+            };
+        }
+
+        let kind = as_str(required(j, "type", "material")?, "type")?;
+        match kind {
+            "lambertian" => {
+                check_fields(j, kind, &["type", "albedo"])?;
+                Ok(Arc::new(Lambertian::new(
+                    self.texture(required(j, "albedo", kind)?)?,
+                )))
+            }
+            "metal" => {
+                check_fields(j, kind, &["type", "albedo", "fuzz"])?;
+                let fuzz = j.get("fuzz").map(|f| number(f, "fuzz")).transpose()?;
+                Ok(Arc::new(Metal::new(
+                    vec3(required(j, "albedo", kind)?, "albedo")?,
+                    fuzz.unwrap_or(0.0),
+                )))
+            }
+            "dielectric" => {
+                check_fields(j, kind, &["type", "refraction_index"])?;
+                Ok(Arc::new(Dielectric::new(number(
+                    required(j, "refraction_index", kind)?,
+                    "refraction_index",
+                )?)))
+            }
+            "diffuse_light" => {
+                check_fields(j, kind, &["type", "emit"])?;
+                Ok(Arc::new(DiffuseLight::new(
+                    self.texture(required(j, "emit", kind)?)?,
+                )))
+            }
+            "isotropic" => {
+                check_fields(j, kind, &["type", "albedo"])?;
+                Ok(Arc::new(Isotropic::new(
+                    self.texture(required(j, "albedo", kind)?)?,
+                )))
+            }
+            _ => error(
+                j,
+                format!(
+                    "unknown material type '{kind}' (expected lambertian, metal, dielectric, diffuse_light or isotropic)"
+                ),
+            ),
+        }
+    }
+
+    fn object(&self, j: &Json) -> Result<Arc<dyn Hittable>> {
+        if let JsonValue::String(name) = &j.value {
+            return match self.shapes.get(name) {
+                Some(s) => Ok(s.clone()),
+                None => error(j, format!("unknown shape '{name}'")),
+            };
+        }
+
+        let kind = as_str(required(j, "type", "object")?, "type")?;
+        match kind {
+            "sphere" => {
+                check_fields(j, kind, &["type", "center", "radius", "material"])?;
+                Ok(Arc::new(Sphere::new(
+                    vec3(required(j, "center", kind)?, "center")?,
+                    number(required(j, "radius", kind)?, "radius")?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "moving_sphere" => {
+                check_fields(
+                    j,
+                    kind,
+                    &["type", "center0", "center1", "radius", "material"],
+                )?;
+                Ok(Arc::new(Sphere::new_moving(
+                    vec3(required(j, "center0", kind)?, "center0")?,
+                    vec3(required(j, "center1", kind)?, "center1")?,
+                    number(required(j, "radius", kind)?, "radius")?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "quad" => {
+                check_fields(j, kind, &["type", "q", "u", "v", "material"])?;
+                Ok(Arc::new(Quad::new(
+                    vec3(required(j, "q", kind)?, "q")?,
+                    vec3(required(j, "u", kind)?, "u")?,
+                    vec3(required(j, "v", kind)?, "v")?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            // also takes a quad's q, u and v, keeping the half on q's side of the diagonal
+            "triangle" if j.get("q").is_some() => {
+                check_fields(j, kind, &["type", "q", "u", "v", "material"])?;
+                Ok(Arc::new(PlanarTriangle::new(
+                    vec3(required(j, "q", kind)?, "q")?,
+                    vec3(required(j, "u", kind)?, "u")?,
+                    vec3(required(j, "v", kind)?, "v")?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "triangle" => {
+                check_fields(j, kind, &["type", "a", "b", "c", "material"])?;
+                Ok(Arc::new(Triangle::new(
+                    vec3(required(j, "a", kind)?, "a")?,
+                    vec3(required(j, "b", kind)?, "b")?,
+                    vec3(required(j, "c", kind)?, "c")?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "ellipse" => {
+                check_fields(j, kind, &["type", "center", "u", "v", "material"])?;
+                Ok(Arc::new(Ellipse::new(
+                    vec3(required(j, "center", kind)?, "center")?,
+                    vec3(required(j, "u", kind)?, "u")?,
+                    vec3(required(j, "v", kind)?, "v")?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "annulus" => {
+                check_fields(j, kind, &["type", "center", "u", "v", "inner", "material"])?;
+                let inner_json = required(j, "inner", kind)?;
+                let inner = number(inner_json, "inner")?;
+                if !(0.0..1.0).contains(&inner) {
+                    return error(inner_json, "inner: must be at least 0 and less than 1");
+                }
+                Ok(Arc::new(Annulus::new(
+                    vec3(required(j, "center", kind)?, "center")?,
+                    vec3(required(j, "u", kind)?, "u")?,
+                    vec3(required(j, "v", kind)?, "v")?,
+                    inner,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "disk" => {
+                check_fields(
+                    j,
+                    kind,
+                    &[
+                        "type",
+                        "center",
+                        "normal",
+                        "radius",
+                        "inner_radius",
+                        "material",
+                    ],
+                )?;
+                let normal_json = required(j, "normal", kind)?;
+                let normal = vec3(normal_json, "normal")?;
+                if normal.length_squared() == 0.0 {
+                    return error(normal_json, "normal: must not be zero");
+                }
+                let radius = positive(required(j, "radius", kind)?, "radius")?;
+                let inner_radius = j
+                    .get("inner_radius")
+                    .map(|r| number(r, "inner_radius"))
+                    .transpose()?
+                    .unwrap_or(0.0);
+                if !(0.0..radius).contains(&inner_radius) {
+                    return error(j, "inner_radius: must be at least 0 and less than radius");
+                }
+                Ok(Arc::new(Disk::new(
+                    vec3(required(j, "center", kind)?, "center")?,
+                    normal,
+                    radius,
+                    inner_radius,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "torus" => {
+                check_fields(
+                    j,
+                    kind,
+                    &["type", "center", "major_radius", "minor_radius", "material"],
+                )?;
+                Ok(Arc::new(Torus::new(
+                    vec3(required(j, "center", kind)?, "center")?,
+                    positive(required(j, "major_radius", kind)?, "major_radius")?,
+                    positive(required(j, "minor_radius", kind)?, "minor_radius")?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "cylinder" => {
+                check_fields(
+                    j,
+                    kind,
+                    &["type", "base", "radius", "height", "capped", "material"],
+                )?;
+                Ok(Arc::new(Cylinder::new(
+                    vec3(required(j, "base", kind)?, "base")?,
+                    positive(required(j, "radius", kind)?, "radius")?,
+                    positive(required(j, "height", kind)?, "height")?,
+                    capped(j)?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "cone" => {
+                check_fields(
+                    j,
+                    kind,
+                    &[
+                        "type",
+                        "base",
+                        "radius",
+                        "top_radius",
+                        "height",
+                        "capped",
+                        "material",
+                    ],
+                )?;
+                let top_radius = j
+                    .get("top_radius")
+                    .map(|r| number(r, "top_radius"))
+                    .transpose()?
+                    .unwrap_or(0.0);
+                if top_radius < 0.0 {
+                    return error(j, "top_radius: must not be negative");
+                }
+                Ok(Arc::new(Cone::new(
+                    vec3(required(j, "base", kind)?, "base")?,
+                    positive(required(j, "radius", kind)?, "radius")?,
+                    top_radius,
+                    positive(required(j, "height", kind)?, "height")?,
+                    capped(j)?,
+                    self.material(required(j, "material", kind)?)?,
+                )))
+            }
+            "box" => {
+                check_fields(j, kind, &["type", "a", "b", "material"])?;
+                Ok(make_box(
+                    vec3(required(j, "a", kind)?, "a")?,
+                    vec3(required(j, "b", kind)?, "b")?,
+                    self.material(required(j, "material", kind)?)?,
+                ))
+            }
+            "obj" => {
+                check_fields(j, kind, &["type", "file", "material"])?;
+                let file = as_str(required(j, "file", kind)?, "file")?;
+                let default_material: Arc<dyn Material> = match j.get("material") {
+                    Some(m) => self.material(m)?,
+                    None => Arc::new(Lambertian::new_color(Vec3::new(0.73, 0.73, 0.73))),
+                };
+                let model = match load_obj(self.dir.join(file), default_material) {
+                    Ok(model) => model,
+                    Err(e) => return error(j, format!("failed to load '{file}': {e}")),
+                };
+                Ok(Arc::new(BvhNode::new_from_list_with(
+                    &mut model.triangles(),
+                    &self.bvh_config,
+                )))
+            }
+            "constant_medium" => {
+                check_fields(j, kind, &["type", "boundary", "density", "albedo"])?;
+                Ok(Arc::new(ConstantMedium::new(
+                    self.object(required(j, "boundary", kind)?)?,
+                    number(required(j, "density", kind)?, "density")?,
+                    self.texture(required(j, "albedo", kind)?)?,
+                )))
+            }
+            "translate" => {
+                check_fields(j, kind, &["type", "offset", "object"])?;
+                Ok(Arc::new(Translate::new(
+                    self.object(required(j, "object", kind)?)?,
+                    vec3(required(j, "offset", kind)?, "offset")?,
+                )))
+            }
+            "rotate_y" => {
+                check_fields(j, kind, &["type", "angle", "object"])?;
+                Ok(Arc::new(RotateY::new(
+                    self.object(required(j, "object", kind)?)?,
+                    number(required(j, "angle", kind)?, "angle")?,
+                )))
+            }
+            "rotate_x" => {
+                check_fields(j, kind, &["type", "angle", "object"])?;
+                Ok(Arc::new(RotateX::new(
+                    self.object(required(j, "object", kind)?)?,
+                    number(required(j, "angle", kind)?, "angle")?,
+                )))
+            }
+            "rotate_z" => {
+                check_fields(j, kind, &["type", "angle", "object"])?;
+                Ok(Arc::new(RotateZ::new(
+                    self.object(required(j, "object", kind)?)?,
+                    number(required(j, "angle", kind)?, "angle")?,
+                )))
+            }
+            "scale" => {
+                check_fields(j, kind, &["type", "scale", "object"])?;
+                let scale_json = required(j, "scale", kind)?;
+                let scale = vec3(scale_json, "scale")?;
+                if (0..3).any(|c| scale[c] == 0.0) {
+                    return error(scale_json, "scale: components must be nonzero");
+                }
+                Ok(Arc::new(Scale::new(
+                    self.object(required(j, "object", kind)?)?,
+                    scale,
+                )))
+            }
+            "transform" => {
+                check_fields(
+                    j,
+                    kind,
+                    &[
+                        "type", "matrix", "scale", "axis", "angle", "offset", "object",
+                    ],
+                )?;
+                let matrix = match j.get("matrix") {
+                    Some(m) => {
+                        if ["scale", "axis", "angle", "offset"]
+                            .iter()
+                            .any(|k| j.get(k).is_some())
+                        {
+                            return error(
+                                j,
+                                "transform: give either matrix or scale/axis/angle/offset",
+                            );
+                        }
+                        let matrix = mat4(m, "matrix")?;
+                        if !matrix.is_affine() {
+                            return error(m, "matrix: last row must be [0, 0, 0, 1]");
+                        }
+                        matrix
+                    }
+                    // scaled first, then rotated, then moved
+                    None => {
+                        let mut matrix = Mat4::identity();
+                        if let Some(scale) = j.get("scale") {
+                            matrix = Mat4::scaling(&vec3(scale, "scale")?);
+                        }
+                        if let Some(angle) = j.get("angle") {
+                            let axis = vec3(required(j, "axis", kind)?, "axis")?;
+                            if axis.length_squared() == 0.0 {
+                                return error(j, "axis: must not be zero");
+                            }
+                            matrix = Mat4::rotation(&axis, number(angle, "angle")?) * matrix;
+                        }
+                        if let Some(offset) = j.get("offset") {
+                            matrix = Mat4::translation(&vec3(offset, "offset")?) * matrix;
+                        }
+                        matrix
+                    }
+                };
+                if matrix.inverse().is_none() {
+                    return error(j, "transform: matrix is not invertible");
+                }
+                Ok(Arc::new(Transform::new(
+                    self.object(required(j, "object", kind)?)?,
+                    matrix,
+                )))
+            }
+            "animated_transform" => {
+                check_fields(j, kind, &["type", "keyframes", "object"])?;
+                let keys_json = required(j, "keyframes", kind)?;
+                let mut keys = Vec::new();
+                for k in as_array(keys_json, "keyframes")? {
+                    keys.push(keyframe(k)?);
+                }
+                if keys.is_empty() {
+                    return error(keys_json, "keyframes: expected at least one");
+                }
+                Ok(Arc::new(AnimatedTransform::new(
+                    self.object(required(j, "object", kind)?)?,
+                    keys,
+                )))
+            }
+            "list" => {
+                check_fields(j, kind, &["type", "objects"])?;
+                let mut list = HittableList::new();
+                for o in as_array(required(j, "objects", kind)?, "objects")? {
+                    list.add(self.object(o)?);
+                }
+                Ok(Arc::new(BvhNode::new_from_list_with(
+                    &mut list,
+                    &self.bvh_config,
+                )))
+            }
+            _ => error(
+                j,
+                format!(
+                    "unknown object type '{kind}' (expected sphere, moving_sphere, quad, triangle, ellipse, annulus, disk, torus, cylinder, cone, box, obj, constant_medium, translate, rotate_x, rotate_y, rotate_z, scale, transform, animated_transform or list)"
+                ),
+            ),
+        }
+    }
+}
+
+fn camera(j: &Json) -> Result<Camera> {
+    let mut cam = Camera::default();
+    for (key, v) in as_object(j, "camera")? {
+        match key.as_str() {
+            "aspect_ratio" => cam.aspect_ratio = positive(v, key)?,
+            "image_width" => cam.image_width = integer(v, key)?,
+            "samples_per_pixel" => cam.samples_per_pixel = integer(v, key)?,
+            "max_samples_per_pixel" => cam.max_samples_per_pixel = integer(v, key)?,
+            "pass_samples" => cam.pass_samples = integer(v, key)?,
+            "tile_size" => cam.tile_size = integer(v, key)?,
+            "tile_order" => {
+                let name = as_str(v, key)?;
+                cam.tile_order = match TileOrder::from_name(name) {
+                    Some(order) => order,
+                    None => {
+                        return error(
+                            v,
+                            format!(
+                                "unknown tile_order '{name}' (expected one of: {})",
+                                TileOrder::NAMES
+                            ),
+                        );
+                    }
+                };
+            }
+            "adaptive_threshold" => cam.adaptive_threshold = positive(v, key)?,
+            "max_depth" => cam.max_depth = integer(v, key)?,
+            "rr_min_depth" => cam.rr_min_depth = integer(v, key)?,
+            "seed" => cam.seed = u64::from(integer(v, key)?),
+            "vfov" => cam.vfov = positive(v, key)?,
+            "lookfrom" => cam.lookfrom = vec3(v, key)?,
+            "lookat" => cam.lookat = vec3(v, key)?,
+            "vup" => cam.vup = vec3(v, key)?,
+            "defocus_angle" => cam.defocus_angle = number(v, key)?,
+            "focus_dist" => cam.focus_dist = positive(v, key)?,
+            "background" => cam.background = vec3(v, key)?,
+            "tone_map" => {
+                let name = as_str(v, key)?;
+                cam.tone_mapping.operator = match ToneMap::from_name(name) {
+                    Some(op) => op,
+                    None => {
+                        return error(
+                            v,
+                            format!(
+                                "unknown tone_map '{name}' (expected one of: {})",
+                                ToneMap::NAMES
+                            ),
+                        );
+                    }
+                };
+            }
+            "mis" => {
+                let name = as_str(v, key)?;
+                cam.mis_heuristic = match MisHeuristic::from_name(name) {
+                    Some(h) => h,
+                    None => {
+                        return error(
+                            v,
+                            format!("unknown mis '{name}' (expected balance or power)"),
+                        );
+                    }
+                };
+            }
+            "sampler" => {
+                let name = as_str(v, key)?;
+                cam.sampler = match SamplerKind::from_name(name) {
+                    Some(kind) => kind,
+                    None => {
+                        return error(
+                            v,
+                            format!(
+                                "unknown sampler '{name}' (expected one of: {})",
+                                SamplerKind::NAMES
+                            ),
+                        );
+                    }
+                };
+            }
+            "exposure" => cam.tone_mapping.exposure = number(v, key)?,
+            "white_point" => cam.tone_mapping.white_point = positive(v, key)?,
+            _ => return error(v, format!("unknown camera field '{key}'")),
+        }
+    }
+    if cam.image_width == 0 || cam.samples_per_pixel == 0 {
+        return error(j, "image_width and samples_per_pixel must be at least 1");
+    }
+    Ok(cam)
+}
+
+fn required<'a>(j: &'a Json, key: &str, what: &str) -> Result<&'a Json> {
+    match j.get(key) {
+        Some(v) => Ok(v),
+        None => error(j, format!("missing field '{key}' in {what}")),
+    }
+}
+
+fn check_fields(j: &Json, what: &str, allowed: &[&str]) -> Result<()> {
+    for (key, v) in as_object(j, what)? {
+        if !allowed.contains(&key.as_str()) {
+            return error(
+                v,
+                format!(
+                    "unknown field '{key}' in {what} (expected one of: {})",
+                    allowed.join(", ")
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn as_object<'a>(j: &'a Json, what: &str) -> Result<&'a [(String, Json)]> {
+    match &j.value {
+        JsonValue::Object(fields) => Ok(fields),
+        _ => error(
+            j,
+            format!("{what}: expected an object, found {}", j.type_name()),
+        ),
+    }
+}
+
+fn as_array<'a>(j: &'a Json, what: &str) -> Result<&'a [Json]> {
+    match &j.value {
+        JsonValue::Array(items) => Ok(items),
+        _ => error(
+            j,
+            format!("{what}: expected an array, found {}", j.type_name()),
+        ),
+    }
+}
+
+fn as_str<'a>(j: &'a Json, what: &str) -> Result<&'a str> {
+    match &j.value {
+        JsonValue::String(s) => Ok(s),
+        _ => error(
+            j,
+            format!("{what}: expected a string, found {}", j.type_name()),
+        ),
+    }
+}
+
+fn as_bool(j: &Json, what: &str) -> Result<bool> {
+    match j.value {
+        JsonValue::Bool(b) => Ok(b),
+        _ => error(
+            j,
+            format!("{what}: expected true or false, found {}", j.type_name()),
+        ),
+    }
+}
+
+fn number(j: &Json, what: &str) -> Result<f64> {
+    match j.value {
+        JsonValue::Number(x) => Ok(x),
+        _ => error(
+            j,
+            format!("{what}: expected a number, found {}", j.type_name()),
+        ),
+    }
+}
+
+fn positive(j: &Json, what: &str) -> Result<f64> {
+    let x = number(j, what)?;
+    if x <= 0.0 {
+        return error(j, format!("{what}: must be positive, got {x}"));
+    }
+    Ok(x)
+}
+
+fn integer(j: &Json, what: &str) -> Result<u32> {
+    let x = number(j, what)?;
+    if x < 0.0 || x.fract() != 0.0 || x > u32::MAX as f64 {
+        return error(
+            j,
+            format!("{what}: expected a non-negative integer, got {x}"),
+        );
+    }
+    Ok(x as u32)
+}
+
+fn vec3(j: &Json, what: &str) -> Result<Vec3> {
+    let items = as_array(j, what)?;
+    if items.len() != 3 {
+        return error(
+            j,
+            format!("{what}: expected 3 numbers, found {}", items.len()),
+        );
+    }
+    Ok(Vec3::new(
+        number(&items[0], what)?,
+        number(&items[1], what)?,
+        number(&items[2], what)?,
+    ))
+}
+
+// Four rows of four numbers.
+fn mat4(j: &Json, what: &str) -> Result<Mat4> {
+    let rows = as_array(j, what)?;
+    if rows.len() != 4 {
+        return error(j, format!("{what}: expected 4 rows, found {}", rows.len()));
+    }
+    let mut m = Mat4::identity();
+    for (i, row) in rows.iter().enumerate() {
+        let items = as_array(row, what)?;
+        if items.len() != 4 {
+            return error(
+                row,
+                format!("{what}: expected 4 numbers per row, found {}", items.len()),
+            );
+        }
+        for (k, item) in items.iter().enumerate() {
+            m.m[i][k] = number(item, what)?;
+        }
+    }
+    Ok(m)
+}
+
+fn keyframe(j: &Json) -> Result<Keyframe> {
+    let what = "keyframe";
+    check_fields(j, what, &["time", "scale", "axis", "angle", "offset"])?;
+    let time = number(required(j, "time", what)?, "time")?;
+    let scale = match j.get("scale") {
+        Some(scale_json) => {
+            let scale = vec3(scale_json, "scale")?;
+            if (0..3).any(|c| scale[c] <= 0.0) {
+                return error(scale_json, "scale: components must be positive");
+            }
+            scale
+        }
+        None => Vec3::new(1.0, 1.0, 1.0),
+    };
+    let rotation = match j.get("angle") {
+        Some(angle) => {
+            let axis = vec3(required(j, "axis", what)?, "axis")?;
+            if axis.length_squared() == 0.0 {
+                return error(j, "axis: must not be zero");
+            }
+            Quat::from_axis_angle(&axis, number(angle, "angle")?)
+        }
+        None => Quat::identity(),
+    };
+    let offset = match j.get("offset") {
+        Some(offset) => vec3(offset, "offset")?,
+        None => Vec3::default(),
+    };
+    Ok(Keyframe::new(time, offset, rotation, scale))
+}
+
+// Cylinders and cones are closed unless `"capped": false`.
+fn capped(j: &Json) -> Result<bool> {
+    j.get("capped")
+        .map(|c| as_bool(c, "capped"))
+        .transpose()
+        .map(|c| c.unwrap_or(true))
+}
@@ -5,6 +5,8 @@ use super::material::Material;
 use super::ray::Ray;
 use crate::utils::aabb::Aabb;
 use crate::utils::interval::Interval;
+use crate::utils::onb::Onb;
+use crate::utils::sampler::Sampler;
 use crate::utils::vec3::*;
 
 pub struct Sphere {
@@ -42,6 +44,16 @@ impl Sphere {
         }
     }
 
+    // dp/du and dp/dv for the mapping of `get_sphere_uv`, at unit normal `n`; dp/du
+    // vanishes at the poles.
+    fn sphere_derivatives(&self, n: &Vec3) -> (Vec3, Vec3) {
+        use std::f64::consts::PI;
+        let ring = (n.x() * n.x() + n.z() * n.z()).sqrt().max(1e-12);
+        let dpdu = 2.0 * PI * self.radius * Vec3::new(n.z(), 0.0, -n.x());
+        let dpdv = PI * self.radius * Vec3::new(-n.x() * n.y() / ring, ring, -n.y() * n.z() / ring);
+        (dpdu, dpdv)
+    }
+
     fn get_sphere_uv(p: &Point3) -> (f64, f64) {
         let theta = f64::acos(-p.y());
         let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
@@ -50,10 +62,22 @@ impl Sphere {
             theta / std::f64::consts::PI,
         )
     }
+
+    // Direction inside the cone subtended by a sphere at squared distance `distance_squared`,
+    // around +z, uniform in solid angle.
+    fn random_to_sphere(sampler: &mut dyn Sampler, radius: f64, distance_squared: f64) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
+        let cos_theta_max = (1.0 - radius * radius / distance_squared).sqrt();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let sin_theta = (1.0 - z * z).sqrt();
+        Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z)
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
         let current_center = self.center.at(r.ts);
         let oc = current_center - &r.orig;
         let a = r.dir.length_squared();
@@ -85,10 +109,51 @@ impl Hittable for Sphere {
             self.material.clone(),
         );
         rec.set_face_normal(r, &outward_normal);
+        (rec.dpdu, rec.dpdv) = self.sphere_derivatives(&outward_normal);
         Some(rec)
     }
 
     fn bounding_box(&self) -> Aabb {
         self.aabb
     }
+
+    // Samples the cone of directions towards the sphere rather than its whole area, so no
+    // samples land on the far side. Moving spheres are sampled at their start position.
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        let direction = self.center.at(0.0) - origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return None;
+        }
+        let uvw = Onb::new(&direction);
+        let dir = uvw.transform(&Self::random_to_sphere(
+            sampler,
+            self.radius,
+            distance_squared,
+        ));
+        let rec = self.hit(
+            &Ray::new(*origin, dir, 0.0),
+            &Interval::new(0.001, f64::INFINITY),
+            sampler,
+        )?;
+        let pdf = self.pdf_value(origin, &dir, sampler);
+        (pdf > 0.0).then_some((rec.p, pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        let r = Ray::new(*origin, *direction, 0.0);
+        if self
+            .hit(&r, &Interval::new(0.001, f64::INFINITY), sampler)
+            .is_none()
+        {
+            return 0.0;
+        }
+        let distance_squared = (self.center.at(0.0) - origin).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 0.0;
+        }
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
 }
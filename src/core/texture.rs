@@ -117,8 +117,12 @@ pub struct NoiseTexture {
 
 impl NoiseTexture {
     pub fn new(scale: f64) -> Self {
+        Self::with_seed(scale, 0)
+    }
+
+    pub fn with_seed(scale: f64, seed: u64) -> Self {
         Self {
-            noise: Perlin::new(),
+            noise: Perlin::new(seed),
             scale,
         }
     }
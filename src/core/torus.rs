@@ -0,0 +1,110 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::hittable::{HitRecord, Hittable};
+use super::material::Material;
+use super::ray::Ray;
+use crate::utils::aabb::Aabb;
+use crate::utils::interval::Interval;
+use crate::utils::poly;
+use crate::utils::sampler::Sampler;
+use crate::utils::vec3::{Point3, Vec3};
+
+// Ring around the y axis through `center`: a tube of `minor_radius` swept along a circle
+// of `major_radius`. u goes around the y axis as on a `Sphere`; v goes around the tube,
+// starting at its outer equator and heading up.
+pub struct Torus {
+    pub center: Point3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub material: Arc<dyn Material>,
+    pub aabb: Aabb,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let major_radius = major_radius.max(0.0);
+        let minor_radius = minor_radius.max(0.0);
+        let extent = Vec3::new(
+            major_radius + minor_radius,
+            minor_radius,
+            major_radius + minor_radius,
+        );
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            material,
+            aabb: Aabb::from_points(center - extent, center + extent),
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let (big_r, small_r) = (self.major_radius, self.minor_radius);
+        let speed = r.dir.length();
+        if speed == 0.0 {
+            return None;
+        }
+        let d = r.dir / speed;
+
+        // Solve in distance along the ray from a start just short of the torus, so the
+        // coefficients stay well scaled however far away the ray begins.
+        let bound = big_r + small_r;
+        let to_center = (self.center - r.orig).dot(&d);
+        let start = (to_center - bound).max(0.0);
+        let lo = ray_t.min * speed - start;
+        let hi = (ray_t.max * speed).min(to_center + bound) - start;
+        if lo > hi {
+            return None;
+        }
+        let o = r.orig + start * d - self.center;
+
+        // (|o + s d|^2 + R^2 - r^2)^2 = 4 R^2 ((o + s d).x^2 + (o + s d).z^2)
+        let b = 2.0 * o.dot(&d);
+        let c = o.length_squared() + big_r * big_r - small_r * small_r;
+        let four_r2 = 4.0 * big_r * big_r;
+        let coeffs = [
+            c * c - four_r2 * (o.x() * o.x() + o.z() * o.z()),
+            2.0 * b * c - 2.0 * four_r2 * (o.x() * d.x() + o.z() * d.z()),
+            b * b + 2.0 * c - four_r2 * (d.x() * d.x() + d.z() * d.z()),
+            2.0 * b,
+            1.0,
+        ];
+        let s = poly::roots_in(&coeffs, lo, hi)
+            .into_iter()
+            .find(|&s| s > lo)?;
+        let t = (start + s) / speed;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        // the nearest point on the core circle is straight in along the normal
+        let p = o + s * d;
+        let rho = (p.x() * p.x() + p.z() * p.z()).sqrt();
+        let radial = if rho > 0.0 {
+            Vec3::new(p.x() / rho, 0.0, p.z() / rho)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let outward_normal = (p - big_r * radial) / small_r;
+
+        let u = ((-p.z()).atan2(p.x()) + PI) / (2.0 * PI);
+        let v = p.y().atan2(rho - big_r).rem_euclid(2.0 * PI) / (2.0 * PI);
+        let mut rec = HitRecord::new(r.at(t), t, (u, v), self.material.clone());
+        rec.set_face_normal(r, &outward_normal.unit_vector());
+        rec.dpdu = 2.0 * PI * Vec3::new(p.z(), 0.0, -p.x());
+        rec.dpdv = 2.0 * PI * (-p.y() * radial + Vec3::new(0.0, rho - big_r, 0.0));
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+}
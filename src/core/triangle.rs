@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use super::hittable::{HitRecord, Hittable};
+use super::hittable_list::HittableList;
+use super::material::Material;
+use super::ray::Ray;
+use crate::utils::aabb::Aabb;
+use crate::utils::interval::Interval;
+use crate::utils::sampler::Sampler;
+use crate::utils::vec3::{Point3, Vec3};
+
+// Indexed triangle mesh: vertex attributes are stored once and shared by every face.
+// `normals` and `uvs` are optional; when present they are indexed like `positions`.
+pub struct TriangleMesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Arc<dyn Material>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f64, f64)>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<dyn Material>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            positions,
+            normals,
+            uvs,
+            indices,
+            material,
+        })
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn has_normals(&self) -> bool {
+        self.normals.len() == self.positions.len()
+    }
+
+    pub fn has_uvs(&self) -> bool {
+        self.uvs.len() == self.positions.len()
+    }
+
+    // One hittable per face, ready to be fed into `BvhNode`.
+    pub fn triangles(mesh: &Arc<Self>) -> HittableList {
+        let mut list = HittableList::new();
+        for face in 0..mesh.face_count() {
+            list.add(Arc::new(Triangle::from_mesh(mesh.clone(), face)));
+        }
+        list
+    }
+}
+
+pub struct Triangle {
+    pub mesh: Arc<TriangleMesh>,
+    pub face: usize,
+    pub aabb: Aabb,
+}
+
+impl Triangle {
+    // Standalone triangle backed by a single-face mesh.
+    pub fn new(a: Point3, b: Point3, c: Point3, material: Arc<dyn Material>) -> Self {
+        let mesh = TriangleMesh::new(
+            vec![a, b, c],
+            Vec::new(),
+            Vec::new(),
+            vec![[0, 1, 2]],
+            material,
+        );
+        Self::from_mesh(mesh, 0)
+    }
+
+    pub fn from_mesh(mesh: Arc<TriangleMesh>, face: usize) -> Self {
+        let [i0, i1, i2] = mesh.indices[face];
+        let (p0, p1, p2) = (mesh.positions[i0], mesh.positions[i1], mesh.positions[i2]);
+        let aabb = Aabb::from_points(p0, p1).merge(&Aabb::from_points(p2, p2));
+        Self { mesh, face, aabb }
+    }
+
+    fn vertices(&self) -> (Point3, Point3, Point3) {
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+        (
+            self.mesh.positions[i0],
+            self.mesh.positions[i1],
+            self.mesh.positions[i2],
+        )
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        // Möller–Trumbore
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+        let p0 = self.mesh.positions[i0];
+        let e1 = self.mesh.positions[i1] - p0;
+        let e2 = self.mesh.positions[i2] - p0;
+
+        let pvec = r.dir.cross(&e2);
+        let det = e1.dot(&pvec);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let tvec = r.orig - p0;
+        let b1 = tvec.dot(&pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let qvec = tvec.cross(&e1);
+        let b2 = r.dir.dot(&qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = e2.dot(&qvec) * inv_det;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        // barycentric weights for vertices 0, 1, 2
+        let b0 = 1.0 - b1 - b2;
+        let uv = if self.mesh.has_uvs() {
+            let (uv0, uv1, uv2) = (self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]);
+            (
+                b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+            )
+        } else {
+            (b1, b2)
+        };
+
+        let mut rec = HitRecord::new(r.at(t), t, uv, self.mesh.material.clone());
+        let geometric_normal = e1.cross(&e2).unit_vector();
+        rec.set_face_normal(r, &geometric_normal);
+        (rec.dpdu, rec.dpdv) = (e1, e2);
+        if self.mesh.has_uvs() {
+            // solve e1 = du1 dpdu + dv1 dpdv, e2 = du2 dpdu + dv2 dpdv; keep the edges if
+            // the UVs are degenerate
+            let (uv0, uv1, uv2) = (self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]);
+            let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+            let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+            let uv_det = du1 * dv2 - dv1 * du2;
+            if uv_det.abs() > 1e-12 {
+                rec.dpdu = (dv2 * e1 - dv1 * e2) / uv_det;
+                rec.dpdv = (du1 * e2 - du2 * e1) / uv_det;
+            }
+        }
+
+        if self.mesh.has_normals() {
+            // interpolated shading normal
+            let n = b0 * self.mesh.normals[i0]
+                + b1 * self.mesh.normals[i1]
+                + b2 * self.mesh.normals[i2];
+            if !n.near_zero() {
+                rec.set_shading_normal(r, &n);
+            }
+        }
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.aabb
+    }
+
+    fn sample_surface(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Option<(Point3, f64)> {
+        // uniform over the area: sqrt warps the unit square onto the triangle
+        let (p0, p1, p2) = self.vertices();
+        let (r1, r2) = sampler.get_2d();
+        let su = r1.sqrt();
+        let b2 = r2 * su;
+        let p = p0 + (1.0 - su) * (p1 - p0) + b2 * (p2 - p0);
+        let pdf = self.pdf_value(origin, &(p - origin), sampler);
+        (pdf > 0.0).then_some((p, pdf))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, sampler: &mut dyn Sampler) -> f64 {
+        let r = Ray::new(*origin, *direction, 0.0);
+        let Some(rec) = self.hit(&r, &Interval::new(0.001, f64::INFINITY), sampler) else {
+            return 0.0;
+        };
+        let (p0, p1, p2) = self.vertices();
+        let n = (p1 - p0).cross(&(p2 - p0));
+        let area = 0.5 * n.length();
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(&n) / (direction.length() * n.length())).abs();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+        distance_squared / (cosine * area)
+    }
+}
@@ -1,18 +1,33 @@
 mod core;
 mod utils;
 
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
-use crate::core::camera::Camera;
+use crate::core::camera::{Camera, MisHeuristic, RenderProgress};
 use crate::core::hittable::{Hittable, RotateY, Translate};
 use crate::core::hittable_list::HittableList;
-use crate::core::material::{Dielectric, DiffuseLight, Lambertian, Metal};
+use crate::core::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
 use crate::core::quad::{Quad, make_box};
+use crate::core::scene::load_scene;
 use crate::core::sphere::Sphere;
 use crate::core::texture::{CheckerTexture, ImageTexture, NoiseTexture};
-use crate::utils::bvh::BvhNode;
+use crate::core::triangle::{Triangle, TriangleMesh};
+use crate::utils::bvh::{BvhConfig, BvhNode};
+use crate::utils::cancel::CancelToken;
+use crate::utils::checkpoint::{Fnv64, hash_camera, read_checkpoint, write_checkpoint};
 use crate::utils::color::Color;
+use crate::utils::exr::{ExrCompression, ExrPixelType};
+use crate::utils::obj::load_obj;
+use crate::utils::output::{ImageFormat, OutputOptions, save_image};
+use crate::utils::sampler::{IndependentSampler, Sampler, SamplerKind};
+use crate::utils::tiles::TileOrder;
 use crate::utils::timer::Timer;
+use crate::utils::tonemap::ToneMap;
 use crate::utils::vec3::*;
 
 fn bouncing_spheres() {
@@ -31,18 +46,21 @@ fn bouncing_spheres() {
         Arc::new(Lambertian::new(checker)),
     )));
 
+    // the layout follows --seed too, so a seed pins down the whole image
+    let mut rng = IndependentSampler::new(seed());
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = rand::random::<f64>();
+            let choose_mat = rng.get_1d();
             let center = Point3::new(
-                a as f64 + 0.9 * rand::random::<f64>(),
+                a as f64 + 0.9 * rng.get_1d(),
                 0.2,
-                b as f64 + 0.9 * rand::random::<f64>(),
+                b as f64 + 0.9 * rng.get_1d(),
             );
             if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
                 let sphere: Arc<dyn Hittable> = if choose_mat < 0.8 {
-                    let albedo = Color::random(0.0, 1.0) * Color::random(0.0, 1.0);
-                    let center1 = center + Vec3::new(0.0, rand::random::<f64>() * 0.5, 0.0);
+                    let albedo =
+                        Color::random(&mut rng, 0.0, 1.0) * Color::random(&mut rng, 0.0, 1.0);
+                    let center1 = center + Vec3::new(0.0, rng.get_1d() * 0.5, 0.0);
                     Arc::new(Sphere::new_moving(
                         center,
                         center1,
@@ -50,8 +68,8 @@ fn bouncing_spheres() {
                         Arc::new(Lambertian::new_color(albedo)),
                     ))
                 } else if choose_mat < 0.95 {
-                    let albedo = Color::random(0.5, 1.0);
-                    let fuzz = rand::random::<f64>() * 0.5;
+                    let albedo = Color::random(&mut rng, 0.5, 1.0);
+                    let fuzz = rng.get_1d() * 0.5;
                     Arc::new(Sphere::new(center, 0.2, Arc::new(Metal::new(albedo, fuzz))))
                 } else {
                     Arc::new(Sphere::new(center, 0.2, Arc::new(Dielectric::new(1.5))))
@@ -84,9 +102,14 @@ fn bouncing_spheres() {
 
     let mut timer = Timer::new();
     timer.start();
-    let world_bvh = BvhNode::new_from_list(&mut world);
+    let bvh_config = bvh_config();
+    let world_bvh = BvhNode::new_from_list_with(&mut world, &bvh_config);
     timer.stop();
-    println!("BVH build time: {} ms", timer.elapsed_ms_3dp());
+    println!(
+        "BVH build time ({:?}): {} ms",
+        bvh_config.split,
+        timer.elapsed_ms_3dp()
+    );
 
     // Camera
     let mut cam = Camera::new(
@@ -104,17 +127,7 @@ fn bouncing_spheres() {
     );
 
     // Render to file
-    let file = std::fs::File::create("image-1.ppm").unwrap();
-    timer.start();
-    cam.render(file, &world_bvh).unwrap();
-    timer.stop();
-    let render_ms = timer.elapsed_ms();
-    println!(
-        "Render time: {:.3} ms ({:.3} s)",
-        render_ms,
-        render_ms / 1000.0
-    );
-    println!("Image saved to image-1.ppm");
+    render_to_file(&mut cam, &world_bvh, &HittableList::new(), "image-1.ppm");
 }
 
 fn checkered_spheres() {
@@ -150,18 +163,7 @@ fn checkered_spheres() {
         Color::new(0.7, 0.8, 1.0),
     );
 
-    let file = std::fs::File::create("image-2.ppm").unwrap();
-    let mut timer = Timer::new();
-    timer.start();
-    cam.render(file, &world).unwrap();
-    timer.stop();
-    let render_ms = timer.elapsed_ms();
-    println!(
-        "Render time: {:.3} ms ({:.3} s)",
-        render_ms,
-        render_ms / 1000.0
-    );
-    println!("Image saved to image-2.ppm");
+    render_to_file(&mut cam, &world, &HittableList::new(), "image-2.ppm");
 }
 
 fn earth() {
@@ -183,18 +185,7 @@ fn earth() {
         Color::new(0.7, 0.8, 1.0),
     );
 
-    let file = std::fs::File::create("image-3.ppm").unwrap();
-    let mut timer = Timer::new();
-    timer.start();
-    cam.render(file, &*globe).unwrap();
-    timer.stop();
-    let render_ms = timer.elapsed_ms();
-    println!(
-        "Render time: {:.3} ms ({:.3} s)",
-        render_ms,
-        render_ms / 1000.0
-    );
-    println!("Image saved to image-3.ppm");
+    render_to_file(&mut cam, &*globe, &HittableList::new(), "image-3.ppm");
 }
 
 fn perlin_spheres() {
@@ -226,18 +217,7 @@ fn perlin_spheres() {
         Color::new(0.7, 0.8, 1.0),
     );
 
-    let file = std::fs::File::create("image-4.ppm").unwrap();
-    let mut timer = Timer::new();
-    timer.start();
-    cam.render(file, &world).unwrap();
-    timer.stop();
-    let render_ms = timer.elapsed_ms();
-    println!(
-        "Render time: {:.3} ms ({:.3} s)",
-        render_ms,
-        render_ms / 1000.0
-    );
-    println!("Image saved to image-4.ppm");
+    render_to_file(&mut cam, &world, &HittableList::new(), "image-4.ppm");
 }
 
 fn quads() {
@@ -296,18 +276,7 @@ fn quads() {
         Color::new(0.7, 0.8, 1.0),
     );
 
-    let file = std::fs::File::create("image-5.ppm").unwrap();
-    let mut timer = Timer::new();
-    timer.start();
-    cam.render(file, &world).unwrap();
-    timer.stop();
-    let render_ms = timer.elapsed_ms();
-    println!(
-        "Render time: {:.3} ms ({:.3} s)",
-        render_ms,
-        render_ms / 1000.0
-    );
-    println!("Image saved to image-5.ppm");
+    render_to_file(&mut cam, &world, &HittableList::new(), "image-5.ppm");
 }
 
 fn simple_light() {
@@ -326,17 +295,21 @@ fn simple_light() {
     )));
 
     let difflight = Arc::new(DiffuseLight::new_color(Color::new(4.0, 4.0, 4.0)));
-    world.add(Arc::new(Sphere::new(
+    let mut lights = HittableList::new();
+    lights.add(Arc::new(Sphere::new(
         Point3::new(0.0, 7.0, 0.0),
         2.0,
         difflight.clone(),
     )));
-    world.add(Arc::new(Quad::new(
+    lights.add(Arc::new(Quad::new(
         Point3::new(3.0, 1.0, -2.0),
         Vec3::new(2.0, 0.0, 0.0),
         Vec3::new(0.0, 2.0, 0.0),
         difflight,
     )));
+    for light in &lights.objects {
+        world.add(light.clone());
+    }
 
     let mut cam = Camera::new(
         16.0 / 9.0,
@@ -352,18 +325,7 @@ fn simple_light() {
         Color::new(0.0, 0.0, 0.0),
     );
 
-    let file = std::fs::File::create("image-6.ppm").unwrap();
-    let mut timer = Timer::new();
-    timer.start();
-    cam.render(file, &world).unwrap();
-    timer.stop();
-    let render_ms = timer.elapsed_ms();
-    println!(
-        "Render time: {:.3} ms ({:.3} s)",
-        render_ms,
-        render_ms / 1000.0
-    );
-    println!("Image saved to image-6.ppm");
+    render_to_file(&mut cam, &world, &lights, "image-6.ppm");
 }
 
 fn cornell_box() {
@@ -387,12 +349,15 @@ fn cornell_box() {
         Vec3::new(0.0, 0.0, 555.0),
         red.clone(),
     )));
-    world.add(Arc::new(Quad::new(
+    let light_quad: Arc<dyn Hittable> = Arc::new(Quad::new(
         Point3::new(343.0, 554.0, 332.0),
         Vec3::new(-130.0, 0.0, 0.0),
         Vec3::new(0.0, 0.0, -105.0),
-        light.clone(),
-    )));
+        light,
+    ));
+    world.add(light_quad.clone());
+    let mut lights = HittableList::new();
+    lights.add(light_quad);
     world.add(Arc::new(Quad::new(
         Point3::new(0.0, 0.0, 0.0),
         Vec3::new(555.0, 0.0, 0.0),
@@ -431,6 +396,17 @@ fn cornell_box() {
     box2 = Arc::new(Translate::new(box2, Vec3::new(130.0, 0.0, 65.0)));
     world.add(box2);
 
+    let mut timer = Timer::new();
+    timer.start();
+    let bvh_config = bvh_config();
+    let world = BvhNode::new_from_list_with(&mut world, &bvh_config);
+    timer.stop();
+    println!(
+        "BVH build time ({:?}): {} ms",
+        bvh_config.split,
+        timer.elapsed_ms_3dp()
+    );
+
     let mut cam = Camera::new(
         1.0,
         600,
@@ -445,22 +421,449 @@ fn cornell_box() {
         Color::new(0.0, 0.0, 0.0),
     );
 
-    let file = std::fs::File::create("image-7.ppm").unwrap();
+    render_to_file(&mut cam, &world, &lights, "image-7.ppm");
+}
+
+// Latitude/longitude sphere tessellated into an indexed mesh with per-vertex normals and UVs.
+fn uv_sphere_mesh(
+    center: Point3,
+    radius: f64,
+    slices: usize,
+    stacks: usize,
+    material: Arc<dyn Material>,
+) -> Arc<TriangleMesh> {
+    let mut positions = Vec::with_capacity((slices + 1) * (stacks + 1));
+    let mut normals = Vec::with_capacity(positions.capacity());
+    let mut uvs = Vec::with_capacity(positions.capacity());
+    for j in 0..=stacks {
+        let v = j as f64 / stacks as f64;
+        let theta = v * std::f64::consts::PI;
+        for i in 0..=slices {
+            let u = i as f64 / slices as f64;
+            let phi = u * 2.0 * std::f64::consts::PI;
+            // same parameterization as `Sphere::get_sphere_uv`
+            let n = Vec3::new(
+                -phi.cos() * theta.sin(),
+                -theta.cos(),
+                phi.sin() * theta.sin(),
+            );
+            positions.push(center + radius * n);
+            normals.push(n);
+            uvs.push((u, v));
+        }
+    }
+
+    let mut indices = Vec::with_capacity(slices * stacks * 2);
+    for j in 0..stacks {
+        for i in 0..slices {
+            let a = j * (slices + 1) + i;
+            let b = a + slices + 1;
+            indices.push([a, a + 1, b + 1]);
+            indices.push([a, b + 1, b]);
+        }
+    }
+
+    TriangleMesh::new(positions, normals, uvs, indices, material)
+}
+
+fn triangle_mesh() {
+    let mut world = HittableList::new();
+
+    let earth_texture = Arc::new(ImageTexture::new("earthmap.jpg"));
+    let globe = uv_sphere_mesh(
+        Point3::new(0.0, 2.0, 0.0),
+        2.0,
+        64,
+        32,
+        Arc::new(Lambertian::new(earth_texture)),
+    );
+    world.add(Arc::new(BvhNode::new_from_list(
+        &mut TriangleMesh::triangles(&globe),
+    )));
+
+    let ground = Arc::new(Lambertian::new_color(Color::new(0.4, 0.4, 0.4)));
+    world.add(Arc::new(Triangle::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Point3::new(-20.0, 0.0, 20.0),
+        Point3::new(20.0, 0.0, 0.0),
+        ground,
+    )));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        20.0,
+        Point3::new(13.0, 4.0, 3.0),
+        Point3::new(0.0, 2.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+        Color::new(0.7, 0.8, 1.0),
+    );
+
+    render_to_file(&mut cam, &world, &HittableList::new(), "image-8.ppm");
+}
+
+fn obj_model(path: &str) {
+    let default_material = Arc::new(Lambertian::new_color(Color::new(0.73, 0.73, 0.73)));
+    let model = load_obj(path, default_material).unwrap_or_else(|e| panic!("{e}"));
+    println!(
+        "Loaded {}: {} meshes, {} triangles",
+        path,
+        model.meshes.len(),
+        model.face_count()
+    );
+    for m in &model.meshes {
+        println!("  {}: {} triangles", m.name, m.mesh.face_count());
+    }
+
+    let world = BvhNode::new_from_list_with(&mut model.triangles(), &bvh_config());
+
+    // Frame the model: look at the center of its bounding box from the -z side
+    let bbox = world.bounding_box();
+    let center = Point3::new(
+        (bbox.x.min + bbox.x.max) / 2.0,
+        (bbox.y.min + bbox.y.max) / 2.0,
+        (bbox.z.min + bbox.z.max) / 2.0,
+    );
+    let vfov: f64 = 40.0;
+    let half_extent = bbox.x.size().max(bbox.y.size()) / 2.0;
+    let distance = half_extent / (vfov.to_radians() / 2.0).tan() + bbox.z.size() / 2.0;
+    let background = if model.has_emitters {
+        Color::new(0.0, 0.0, 0.0)
+    } else {
+        Color::new(0.7, 0.8, 1.0)
+    };
+
+    let mut cam = Camera::new(
+        1.0,
+        600,
+        200,
+        50,
+        vfov,
+        center - Vec3::new(0.0, 0.0, distance),
+        center,
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+        background,
+    );
+
+    render_to_file(&mut cam, &world, &model.lights(), "image-9.ppm");
+}
+
+fn scene_file(path: &str) {
+    let mut scene = load_scene(path, &bvh_config()).unwrap_or_else(|e| panic!("{e}"));
+    let output = scene.output.unwrap_or_else(|| "image.ppm".to_string());
+    render_to_file(&mut scene.camera, &*scene.world, &scene.lights, &output);
+}
+
+// Renders to `default_output` unless `--output=` names another file; the extension picks
+// the image format.
+fn render_to_file(
+    cam: &mut Camera,
+    world: &dyn Hittable,
+    lights: &HittableList,
+    default_output: &str,
+) {
+    let output = arg_value("output").unwrap_or_else(|| default_output.to_string());
+    // fail before spending minutes on a render we cannot save
+    ImageFormat::from_path(Path::new(&output)).unwrap_or_else(|e| panic!("{e}"));
+    let sample_map = arg_value("sample-map");
+    if let Some(path) = &sample_map {
+        ImageFormat::from_path(Path::new(path)).unwrap_or_else(|e| panic!("{e}"));
+    }
+    let options = output_options(cam);
+    apply_camera_args(cam);
+
+    // progressive renders rewrite the output after a pass once this many seconds have passed
+    // since the last write; 0 writes after every pass
+    let snapshot_secs: f64 = arg_value("snapshot-secs").map_or(0.0, |t| {
+        t.parse()
+            .unwrap_or_else(|_| panic!("invalid --snapshot-secs '{t}'"))
+    });
+    let mut since_snapshot = Timer::new();
+    since_snapshot.start();
+    let mut passes = 0;
+
+    if let Some(path) = arg_value("checkpoint") {
+        let scene_hash = scene_hash(cam);
+        if has_flag("resume") {
+            let (hash, state) =
+                read_checkpoint(&path).unwrap_or_else(|e| panic!("cannot resume from {path}: {e}"));
+            if hash != scene_hash {
+                panic!("{path} was written for a different scene or camera; refusing to resume");
+            }
+            println!("Resuming from {path}");
+            cam.resume_from = Some(state);
+        }
+        // a failed write should not cost the render itself
+        cam.checkpoint = Some(Box::new(move |state| {
+            if let Err(e) = write_checkpoint(&path, scene_hash, state) {
+                eprintln!("\ncannot write checkpoint {path}: {e}");
+            }
+        }));
+    } else if has_flag("resume") {
+        panic!("--resume needs --checkpoint=FILE");
+    }
+    if let Some(secs) = arg_value("checkpoint-secs") {
+        let secs: f64 = secs
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --checkpoint-secs '{secs}'"));
+        cam.checkpoint_interval = Duration::from_secs_f64(secs);
+    }
+
+    // only passes asked for are written out; the ones a time limit adds are not
+    let snapshots = cam.pass_samples > 0;
+    if let Some(secs) = arg_value("time-limit") {
+        let secs: f64 = secs
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --time-limit '{secs}'"));
+        cam.cancel = CancelToken::with_time_limit(Duration::from_secs_f64(secs));
+        // the deadline ends the render, not the sample count; pixels still stop once adaptive
+        // sampling finds them converged
+        cam.max_samples_per_pixel = u32::MAX;
+        // single-sample passes, so the whole frame converges evenly until time runs out
+        if cam.pass_samples == 0 {
+            cam.pass_samples = 1;
+        }
+    }
+    // Enter on a terminal stops the render early and keeps the image so far
+    if io::stdin().is_terminal() {
+        let cancel = cam.cancel.clone();
+        thread::spawn(move || {
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line).is_ok_and(|n| n > 0) {
+                cancel.cancel();
+            }
+        });
+    }
+
     let mut timer = Timer::new();
     timer.start();
-    cam.render(file, &world).unwrap();
+    cam.progress = Some(Box::new(report_progress));
+    let fb = cam.render(world, lights, |fb| {
+        passes += 1;
+        if snapshots && since_snapshot.elapsed().as_secs_f64() >= snapshot_secs {
+            eprintln!();
+            save_image(&output, fb, &options).unwrap_or_else(|e| panic!("{e}"));
+            println!(
+                "Pass {passes} saved to {output} after {:.1} s",
+                timer.elapsed().as_secs_f64()
+            );
+            since_snapshot.start();
+        }
+    });
     timer.stop();
+    eprintln!();
     let render_ms = timer.elapsed_ms();
     println!(
         "Render time: {:.3} ms ({:.3} s)",
         render_ms,
         render_ms / 1000.0
     );
-    println!("Image saved to image-7.ppm");
+
+    if cam.cancel.is_cancelled() {
+        let total: u64 = fb.samples.iter().map(|&n| n as u64).sum();
+        println!(
+            "Stopped early at {:.1} samples per pixel on average",
+            total as f64 / fb.samples.len() as f64
+        );
+    }
+
+    save_image(&output, &fb, &options).unwrap_or_else(|e| panic!("{e}"));
+    println!("Image saved to {output}");
+
+    // samples per pixel as gray levels, to see where adaptive sampling spent its budget
+    if let Some(path) = sample_map {
+        save_image(&path, &fb.sample_map(), &OutputOptions::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        println!("Sample counts saved to {path}");
+    }
+}
+
+// One status line on stderr, rewritten after every tile. Time-limited renders have no pass
+// count to show.
+fn report_progress(p: &RenderProgress) {
+    let passes = match p.time_left {
+        Some(_) => String::new(),
+        None => format!("/{}", p.passes),
+    };
+    eprint!(
+        "\rpass {}{}  tile {}/{}  {:.2} Mrays/s  ETA {:.0} s   ",
+        p.pass,
+        passes,
+        p.tiles_done,
+        p.tiles,
+        p.rays_per_second() / 1e6,
+        p.eta().as_secs_f64()
+    );
+}
+
+// Identifies what is being rendered for checkpoints: the scene arguments, the contents of
+// any scene or model file among them, and the camera.
+fn scene_hash(cam: &Camera) -> u64 {
+    let mut h = Fnv64::default();
+    for arg in positional_args() {
+        h.write(arg.as_bytes());
+        if let Ok(contents) = fs::read(&arg) {
+            h.write(&contents);
+        }
+    }
+    hash_camera(&mut h, cam);
+    h.finish()
+}
+
+fn positional_args() -> Vec<String> {
+    std::env::args()
+        .skip(1)
+        .filter(|a| !a.starts_with("--"))
+        .collect()
+}
+
+fn has_flag(name: &str) -> bool {
+    let flag = format!("--{name}");
+    std::env::args().any(|a| a == flag)
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let prefix = format!("--{name}=");
+    std::env::args().find_map(|a| a.strip_prefix(&prefix).map(str::to_string))
+}
+
+fn seed() -> u64 {
+    arg_value("seed").map_or(0, |s| {
+        s.parse().unwrap_or_else(|_| panic!("invalid --seed '{s}'"))
+    })
+}
+
+// `--bvh=median|sah`, `--bvh-bins=N`, `--bvh-leaf=N`; sah defaults to 16 bins and leaves of
+// up to 4. Scenes built here print their BVH build time and every render its render time,
+// so the two can be compared.
+fn bvh_config() -> BvhConfig {
+    let mut config = match arg_value("bvh").as_deref() {
+        None | Some("median") => BvhConfig::default(),
+        Some("sah") => BvhConfig::sah(),
+        Some(name) => panic!("unknown --bvh '{name}' (expected median or sah)"),
+    };
+    if let Some(n) = arg_value("bvh-bins") {
+        config.bins = n
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --bvh-bins '{n}'"));
+    }
+    if let Some(n) = arg_value("bvh-leaf") {
+        config.max_leaf_size = n
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --bvh-leaf '{n}'"));
+    }
+    config
 }
+
+// Render settings from the command line, overriding the scene's camera.
+fn apply_camera_args(cam: &mut Camera) {
+    if arg_value("seed").is_some() {
+        cam.seed = seed();
+    }
+    if let Some(n) = arg_value("max-spp") {
+        cam.max_samples_per_pixel = n
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --max-spp '{n}'"));
+    }
+    if let Some(n) = arg_value("tile-size") {
+        cam.tile_size = n
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --tile-size '{n}'"));
+    }
+    if let Some(name) = arg_value("tile-order") {
+        cam.tile_order = TileOrder::from_name(&name).unwrap_or_else(|| {
+            panic!(
+                "unknown --tile-order '{name}' (expected one of: {})",
+                TileOrder::NAMES
+            )
+        });
+    }
+    if let Some(n) = arg_value("pass-spp") {
+        cam.pass_samples = n
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --pass-spp '{n}'"));
+    }
+    if let Some(t) = arg_value("adaptive-threshold") {
+        cam.adaptive_threshold = t
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --adaptive-threshold '{t}'"));
+    }
+    if let Some(depth) = arg_value("max-depth") {
+        cam.max_depth = depth
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --max-depth '{depth}'"));
+    }
+    if let Some(depth) = arg_value("rr-min-depth") {
+        cam.rr_min_depth = depth
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --rr-min-depth '{depth}'"));
+    }
+    if let Some(name) = arg_value("mis") {
+        cam.mis_heuristic = MisHeuristic::from_name(&name)
+            .unwrap_or_else(|| panic!("unknown --mis '{name}' (expected balance or power)"));
+    }
+    if let Some(name) = arg_value("sampler") {
+        cam.sampler = SamplerKind::from_name(&name).unwrap_or_else(|| {
+            panic!(
+                "unknown --sampler '{name}' (expected one of: {})",
+                SamplerKind::NAMES
+            )
+        });
+    }
+}
+
+// Output settings from the command line; tone mapping flags override the camera's.
+fn output_options(cam: &Camera) -> OutputOptions {
+    let mut options = OutputOptions {
+        tone_mapping: cam.tone_mapping,
+        ..Default::default()
+    };
+    if let Some(name) = arg_value("tonemap") {
+        options.tone_mapping.operator = ToneMap::from_name(&name).unwrap_or_else(|| {
+            panic!(
+                "unknown --tonemap '{name}' (expected one of: {})",
+                ToneMap::NAMES
+            )
+        });
+    }
+    if let Some(ev) = arg_value("exposure") {
+        options.tone_mapping.exposure = ev
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --exposure '{ev}'"));
+    }
+    if let Some(white) = arg_value("white-point") {
+        options.tone_mapping.white_point = white
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid --white-point '{white}'"));
+    }
+    match arg_value("exr-pixel").as_deref() {
+        Some("half") | None => {}
+        Some("float") => options.exr_pixel_type = ExrPixelType::Float,
+        Some(other) => panic!("unknown --exr-pixel '{other}' (expected half or float)"),
+    }
+    match arg_value("exr-compression").as_deref() {
+        Some("zip") | None => {}
+        Some("none") => options.exr_compression = ExrCompression::None,
+        Some(other) => panic!("unknown --exr-compression '{other}' (expected none or zip)"),
+    }
+    options
+}
+
 fn main() {
-    let scene = std::env::args()
-        .nth(1)
+    let positional = positional_args();
+    if let Some(path) = positional.first().filter(|p| p.ends_with(".json")) {
+        scene_file(path);
+        return;
+    }
+
+    let scene = positional
+        .first()
         .and_then(|s| s.parse::<i32>().ok())
         .unwrap_or(1);
 
@@ -472,6 +875,12 @@ fn main() {
         5 => quads(),
         6 => simple_light(),
         7 => cornell_box(),
+        8 => triangle_mesh(),
+        9 => obj_model(
+            positional
+                .get(1)
+                .map_or("models/cornell_box.obj", String::as_str),
+        ),
         _ => bouncing_spheres(),
     }
 }
@@ -124,6 +124,19 @@ impl Aabb {
         }
     }
 
+    pub fn surface_area(&self) -> f64 {
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
     pub fn pad_to_minimum(&mut self) {
         let delta = 0.0001;
         for axis in [&mut self.x, &mut self.y, &mut self.z] {
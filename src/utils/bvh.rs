@@ -5,13 +5,59 @@ use crate::core::hittable_list::HittableList;
 use crate::core::ray::Ray;
 use crate::utils::aabb::Aabb;
 use crate::utils::interval::Interval;
+use crate::utils::sampler::Sampler;
 
-// TODO: upgrade to index-based tree
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitMethod {
+    // sort along the longest axis and split at the median
+    Median,
+    // binned surface area heuristic
+    Sah,
+}
 
-pub struct BvhNode {
-    left: Option<Arc<dyn Hittable>>,
-    right: Option<Arc<dyn Hittable>>,
+#[derive(Clone, Copy, Debug)]
+pub struct BvhConfig {
+    pub split: SplitMethod,
+    pub bins: usize,          // SAH buckets per axis
+    pub max_leaf_size: usize, // objects allowed in a leaf before splitting is forced
+}
+
+impl Default for BvhConfig {
+    fn default() -> Self {
+        Self {
+            split: SplitMethod::Median,
+            bins: 16,
+            max_leaf_size: 2,
+        }
+    }
+}
+
+impl BvhConfig {
+    pub fn sah() -> Self {
+        Self {
+            split: SplitMethod::Sah,
+            max_leaf_size: 4,
+            ..Default::default()
+        }
+    }
+}
+
+// Deepest tree the traversal stack can hold; the builder emits a leaf instead of going deeper.
+const MAX_DEPTH: usize = 64;
+
+// Flattened node, stored in depth-first order: an interior node's first child directly
+// follows it, the second child lives at `offset`. Leaves own `primitives[offset..offset + count]`.
+#[derive(Clone, Copy)]
+struct LinearNode {
     bbox: Aabb,
+    offset: usize,
+    count: usize, // 0 for interior nodes
+    axis: usize,  // split axis of interior nodes
+}
+
+pub struct BvhNode {
+    nodes: Vec<LinearNode>,
+    primitives: Vec<Arc<dyn Hittable>>,
 }
 
 impl BvhNode {
@@ -19,20 +65,72 @@ impl BvhNode {
         Self::build(&mut list.objects)
     }
 
+    pub fn new_from_list_with(list: &mut HittableList, config: &BvhConfig) -> Self {
+        Self::build_with(&mut list.objects, config)
+    }
+
     pub fn build(objects: &mut [Arc<dyn Hittable>]) -> Self {
-        let mut node = BvhNode {
-            left: None,
-            right: None,
-            bbox: Aabb::default(),
+        Self::build_with(objects, &BvhConfig::default())
+    }
+
+    pub fn build_with(objects: &mut [Arc<dyn Hittable>], config: &BvhConfig) -> Self {
+        let mut bvh = BvhNode {
+            nodes: Vec::with_capacity(2 * objects.len()),
+            primitives: Vec::new(),
         };
+        if !objects.is_empty() {
+            bvh.build_recursive(objects, 0, 0, config);
+        }
+        // the builder partitions in place, so leaf ranges index straight into `objects`
+        bvh.primitives = objects.to_vec();
+        bvh
+    }
 
+    // Appends the subtree for `objects` (which start at `first` in the final primitive
+    // order) and returns the index of its root node.
+    fn build_recursive(
+        &mut self,
+        objects: &mut [Arc<dyn Hittable>],
+        first: usize,
+        depth: usize,
+        config: &BvhConfig,
+    ) -> usize {
         let len = objects.len();
-        if len == 0 {
-            return node;
+        let bbox = Aabb::from_iter(objects.iter().map(|o| o.bounding_box()));
+
+        let node_idx = self.nodes.len();
+        self.nodes.push(LinearNode {
+            bbox,
+            offset: first,
+            count: len,
+            axis: 0,
+        });
+
+        let split = if len == 1 || depth + 1 >= MAX_DEPTH {
+            None
+        } else {
+            match config.split {
+                SplitMethod::Median if len <= config.max_leaf_size.max(1) => None,
+                SplitMethod::Median => Some(Self::split_median(objects, &bbox)),
+                SplitMethod::Sah => Self::split_sah(objects, &bbox, config),
+            }
+        };
+
+        // no split: the node stays a leaf over the whole range
+        if let Some((mid, axis)) = split {
+            let (left_slice, right_slice) = objects.split_at_mut(mid);
+            self.build_recursive(left_slice, first, depth + 1, config);
+            let second = self.build_recursive(right_slice, first + mid, depth + 1, config);
+            let node = &mut self.nodes[node_idx];
+            node.offset = second;
+            node.count = 0;
+            node.axis = axis;
         }
 
-        let bbox = Aabb::from_iter(objects.iter().map(|o| o.bounding_box()));
+        node_idx
+    }
 
+    fn split_median(objects: &mut [Arc<dyn Hittable>], bbox: &Aabb) -> (usize, usize) {
         let axis = bbox.longest_axis();
         let comparator: fn(&Arc<dyn Hittable>, &Arc<dyn Hittable>) -> std::cmp::Ordering =
             match axis {
@@ -41,25 +139,103 @@ impl BvhNode {
                 2 => Self::box_z_compare,
                 _ => unreachable!(),
             };
+        objects.sort_by(comparator);
+        (objects.len() / 2, axis)
+    }
 
-        if len == 1 {
-            node.left = Some(objects[0].clone());
-            node.right = Some(objects[0].clone());
-        } else if len == 2 {
-            node.left = Some(objects[0].clone());
-            node.right = Some(objects[1].clone());
-        } else {
-            objects.sort_by(|a, b| comparator(a, b));
-            let mid = len / 2;
-            let (left_slice, right_slice) = objects.split_at_mut(mid);
-            node.left = Some(Arc::new(BvhNode::build(left_slice)));
-            node.right = Some(Arc::new(BvhNode::build(right_slice)));
+    // Binned SAH: bucket object centroids along each axis, evaluate the cost of every
+    // bucket boundary and partition at the cheapest one. Returns `(mid, axis)`, or `None`
+    // when a leaf is cheaper and small enough.
+    fn split_sah(
+        objects: &mut [Arc<dyn Hittable>],
+        bbox: &Aabb,
+        config: &BvhConfig,
+    ) -> Option<(usize, usize)> {
+        const TRAVERSAL_COST: f64 = 0.125;
+        let len = objects.len();
+        let bins = config.bins.max(2);
+
+        let mut centroids: Vec<_> = objects
+            .iter()
+            .map(|o| o.bounding_box().centroid())
+            .collect();
+        let mut centroid_bounds = Aabb::default();
+        for c in &centroids {
+            centroid_bounds = centroid_bounds.merge(&Aabb {
+                x: Interval::new(c.x(), c.x()),
+                y: Interval::new(c.y(), c.y()),
+                z: Interval::new(c.z(), c.z()),
+            });
+        }
+
+        let bin_of = |c: f64, axis_bounds: &Interval| -> usize {
+            let rel = (c - axis_bounds.min) / axis_bounds.size();
+            ((rel * bins as f64) as usize).min(bins - 1)
+        };
+
+        let mut best: Option<(f64, usize, usize)> = None; // (cost, axis, split bin)
+        for axis in 0..3 {
+            let axis_bounds = *centroid_bounds.axis_interval(axis);
+            if axis_bounds.size() <= 0.0 {
+                continue;
+            }
+
+            let mut counts = vec![0_usize; bins];
+            let mut bounds = vec![Aabb::default(); bins];
+            for (o, c) in objects.iter().zip(&centroids) {
+                let b = bin_of(c[axis], &axis_bounds);
+                counts[b] += 1;
+                bounds[b] = bounds[b].merge(&o.bounding_box());
+            }
+
+            // sweep from the right to get suffix areas, then from the left for the costs
+            let mut right_area = vec![0.0; bins];
+            let mut right_count = vec![0_usize; bins];
+            let mut acc = Aabb::default();
+            let mut n = 0;
+            for b in (1..bins).rev() {
+                acc = acc.merge(&bounds[b]);
+                n += counts[b];
+                right_area[b] = if n > 0 { acc.surface_area() } else { 0.0 };
+                right_count[b] = n;
+            }
+
+            let mut acc = Aabb::default();
+            let mut n = 0;
+            for b in 0..bins - 1 {
+                acc = acc.merge(&bounds[b]);
+                n += counts[b];
+                if n == 0 || right_count[b + 1] == 0 {
+                    continue;
+                }
+                let cost =
+                    acc.surface_area() * n as f64 + right_area[b + 1] * right_count[b + 1] as f64;
+                if best.is_none_or(|(c, _, _)| cost < c) {
+                    best = Some((cost, axis, b));
+                }
+            }
         }
 
-        let lb = node.left.as_ref().unwrap().bounding_box();
-        let rb = node.right.as_ref().unwrap().bounding_box();
-        node.bbox = lb.merge(&rb);
-        node
+        let Some((cost, axis, split_bin)) = best else {
+            // all centroids coincide; fall back to an even split if the leaf would be too big
+            return (len > config.max_leaf_size).then_some((len / 2, bbox.longest_axis()));
+        };
+
+        let cost = TRAVERSAL_COST + cost / bbox.surface_area();
+        if len <= config.max_leaf_size && cost >= len as f64 {
+            return None;
+        }
+
+        let axis_bounds = *centroid_bounds.axis_interval(axis);
+        let mut mid = 0;
+        for i in 0..len {
+            if bin_of(centroids[i][axis], &axis_bounds) <= split_bin {
+                objects.swap(i, mid);
+                centroids.swap(i, mid);
+                mid += 1;
+            }
+        }
+        Some((mid, axis))
     }
 
     fn box_compare(
@@ -88,35 +264,54 @@ impl BvhNode {
 }
 
 impl Hittable for BvhNode {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
-        if !self.bbox.hit(r, Interval::new(ray_t.min, ray_t.max)) {
+    fn hit(&self, r: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        if self.nodes.is_empty() {
             return None;
         }
 
+        let dir_is_neg = [r.dir.x() < 0.0, r.dir.y() < 0.0, r.dir.z() < 0.0];
         let mut best: Option<HitRecord> = None;
-        let mut tmax = ray_t.max;
+        let mut closest = ray_t.max;
 
-        if let Some(left) = &self.left {
-            if let Some(h) = left.hit(r, ray_t) {
-                tmax = h.t;
-                best = Some(h);
-            }
-        }
+        let mut stack = [0_usize; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut node_idx = 0;
 
-        if let Some(right) = &self.right {
-            let right_interval = Interval::new(ray_t.min, tmax);
-            if let Some(h) = right.hit(r, &right_interval) {
-                match &best {
-                    Some(b) if b.t <= h.t => {}
-                    _ => best = Some(h),
+        loop {
+            let node = &self.nodes[node_idx];
+            if node.bbox.hit(r, Interval::new(ray_t.min, closest)) {
+                if node.count > 0 {
+                    for prim in &self.primitives[node.offset..node.offset + node.count] {
+                        if let Some(h) = prim.hit(r, &Interval::new(ray_t.min, closest), sampler) {
+                            closest = h.t;
+                            best = Some(h);
+                        }
+                    }
+                } else {
+                    // visit the child nearer to the ray origin first, defer the other
+                    let (near, far) = if dir_is_neg[node.axis] {
+                        (node.offset, node_idx + 1)
+                    } else {
+                        (node_idx + 1, node.offset)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    node_idx = near;
+                    continue;
                 }
             }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            node_idx = stack[stack_len];
         }
 
         best
     }
 
     fn bounding_box(&self) -> Aabb {
-        self.bbox
+        self.nodes.first().map_or(Aabb::default(), |n| n.bbox)
     }
 }
@@ -0,0 +1,152 @@
+// Checkpoint files for resuming long renders. Little-endian binary:
+//   "RTCK", version u32, scene hash u64, width u32, height u32,
+//   then per pixel, row-major: sum r, g, b f64, luminance mean f64, m2 f64, samples u32.
+// Samplers keep no state between samples beyond the seed, the kind and, for the ones with a
+// fixed pattern size, the samples per pixel, which the scene hash covers, and the per-pixel
+// sample count, which says where each pixel's sequence resumes.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use crate::core::camera::{Camera, RenderState};
+use crate::utils::color::Color;
+use crate::utils::sampler::SamplerKind;
+
+const MAGIC: &[u8; 4] = b"RTCK";
+const VERSION: u32 = 1;
+const HEADER_BYTES: u64 = 4 + 4 + 8 + 4 + 4;
+const PIXEL_BYTES: u64 = 5 * 8 + 4;
+
+// 64-bit FNV-1a.
+pub struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv64 {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_f64(&mut self, x: f64) {
+        self.write(&x.to_le_bytes());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// Every camera setting that changes what a sample computes. Sample counts, tiling and
+// display settings are left out: those can change between runs without invalidating the
+// samples already taken.
+pub fn hash_camera(h: &mut Fnv64, cam: &Camera) {
+    h.write_f64(cam.aspect_ratio);
+    h.write(&cam.image_width.to_le_bytes());
+    h.write(&cam.max_depth.to_le_bytes());
+    h.write(&cam.rr_min_depth.to_le_bytes());
+    for v in [cam.background, cam.lookfrom, cam.lookat, cam.vup] {
+        h.write_f64(v.x());
+        h.write_f64(v.y());
+        h.write_f64(v.z());
+    }
+    h.write_f64(cam.vfov);
+    h.write_f64(cam.defocus_angle);
+    h.write_f64(cam.focus_dist);
+    h.write(format!("{:?} {:?}", cam.mis_heuristic, cam.sampler).as_bytes());
+    h.write(&cam.seed.to_le_bytes());
+    // these lay their patterns out over samples_per_pixel, so other counts give other samples
+    if matches!(cam.sampler, SamplerKind::Stratified | SamplerKind::Cmj) {
+        h.write(&cam.samples_per_pixel.to_le_bytes());
+    }
+}
+
+// Written to a temporary file first and renamed over `path`, so a render killed mid-write
+// leaves the previous checkpoint intact.
+pub fn write_checkpoint(path: &str, scene_hash: u64, state: &RenderState) -> io::Result<()> {
+    let tmp = format!("{path}.tmp");
+    {
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&scene_hash.to_le_bytes())?;
+        out.write_all(&state.width.to_le_bytes())?;
+        out.write_all(&state.height.to_le_bytes())?;
+        for stats in &state.pixels {
+            for x in [
+                stats.sum.x(),
+                stats.sum.y(),
+                stats.sum.z(),
+                stats.mean,
+                stats.m2,
+            ] {
+                out.write_all(&x.to_le_bytes())?;
+            }
+            out.write_all(&stats.n.to_le_bytes())?;
+        }
+        out.flush()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+// The scene hash stored in the checkpoint and the render state.
+pub fn read_checkpoint(path: &str) -> io::Result<(u64, RenderState)> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut input = BufReader::new(file);
+    let mut magic = [0; 4];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC || read_u32(&mut input)? != VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("'{path}' is not a checkpoint"),
+        ));
+    }
+    let scene_hash = read_u64(&mut input)?;
+    let width = read_u32(&mut input)?;
+    let height = read_u32(&mut input)?;
+    // checked before allocating, so a damaged header cannot ask for a huge state
+    let expected = (width as u64)
+        .checked_mul(height as u64)
+        .and_then(|n| n.checked_mul(PIXEL_BYTES))
+        .and_then(|n| n.checked_add(HEADER_BYTES));
+    if expected != Some(len) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("'{path}' is truncated or damaged: {width}x{height} pixels in {len} bytes"),
+        ));
+    }
+    let mut state = RenderState::new(width, height);
+    for stats in &mut state.pixels {
+        let r = read_f64(&mut input)?;
+        let g = read_f64(&mut input)?;
+        let b = read_f64(&mut input)?;
+        stats.sum = Color::new(r, g, b);
+        stats.mean = read_f64(&mut input)?;
+        stats.m2 = read_f64(&mut input)?;
+        stats.n = read_u32(&mut input)?;
+    }
+    Ok((scene_hash, state))
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut b = [0; 4];
+    input.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut b = [0; 8];
+    input.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b))
+}
+
+fn read_f64(input: &mut impl Read) -> io::Result<f64> {
+    Ok(f64::from_bits(read_u64(input)?))
+}
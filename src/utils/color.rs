@@ -1,28 +1,27 @@
-use std::io::Write;
-
 use super::interval::Interval;
 use super::vec3::Vec3;
 
 pub type Color = Vec3;
 
-pub fn write_color<W: Write>(out: &mut W, pixel_color: &Color) -> std::io::Result<()> {
-    let pixel_color = linear_to_gamma(pixel_color);
-    let r = pixel_color.x();
-    let g = pixel_color.y();
-    let b = pixel_color.z();
+// sRGB-encodes a linear color and quantizes it to 8 bits per channel.
+pub fn color_to_rgb8(pixel_color: &Color) -> [u8; 3] {
+    let r = linear_to_srgb(pixel_color.x());
+    let g = linear_to_srgb(pixel_color.y());
+    let b = linear_to_srgb(pixel_color.z());
 
     let interval = Interval::new(0.0, 0.999);
-    let rbyte = (256.0 * interval.clamp(r)) as u32;
-    let gbyte = (256.0 * interval.clamp(g)) as u32;
-    let bbyte = (256.0 * interval.clamp(b)) as u32;
+    let rbyte = (256.0 * interval.clamp(r)) as u8;
+    let gbyte = (256.0 * interval.clamp(g)) as u8;
+    let bbyte = (256.0 * interval.clamp(b)) as u8;
 
-    writeln!(out, "{} {} {}", rbyte, gbyte, bbyte)
+    [rbyte, gbyte, bbyte]
 }
 
-pub fn linear_to_gamma(color: &Color) -> Color {
-    Color::new(
-        color.x().powf(1.0 / 2.2),
-        color.y().powf(1.0 / 2.2),
-        color.z().powf(1.0 / 2.2),
-    )
+// Piecewise sRGB OETF: linear toe near black, 1/2.4 power segment above it.
+pub fn linear_to_srgb(x: f64) -> f64 {
+    if x <= 0.0031308 {
+        12.92 * x.max(0.0)
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
 }
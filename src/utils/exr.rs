@@ -0,0 +1,225 @@
+use std::io::Write;
+
+use super::framebuffer::Framebuffer;
+use super::zlib;
+
+// Single-part scanline OpenEXR writer: R, G, B channels as half or float, stored either
+// uncompressed or with ZIP compression (16-line blocks).
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExrPixelType {
+    Half,
+    Float,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExrCompression {
+    None,
+    Zip,
+}
+
+impl ExrPixelType {
+    fn code(self) -> i32 {
+        match self {
+            ExrPixelType::Half => 1,
+            ExrPixelType::Float => 2,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ExrPixelType::Half => 2,
+            ExrPixelType::Float => 4,
+        }
+    }
+}
+
+impl ExrCompression {
+    fn code(self) -> u8 {
+        match self {
+            ExrCompression::None => 0,
+            ExrCompression::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(self) -> u32 {
+        match self {
+            ExrCompression::None => 1,
+            ExrCompression::Zip => 16,
+        }
+    }
+}
+
+pub fn write_exr<W: Write>(
+    out: &mut W,
+    fb: &Framebuffer,
+    pixel_type: ExrPixelType,
+    compression: ExrCompression,
+) -> std::io::Result<()> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&20000630_i32.to_le_bytes()); // magic
+    header.extend_from_slice(&2_i32.to_le_bytes()); // version 2, single-part scanline
+
+    // channels are stored in alphabetical order
+    let mut chlist = Vec::new();
+    for name in ["B", "G", "R"] {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&pixel_type.code().to_le_bytes());
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear + reserved
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // x sampling
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // y sampling
+    }
+    chlist.push(0);
+
+    let mut window = Vec::new();
+    for v in [0, 0, fb.width as i32 - 1, fb.height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+
+    write_attribute(&mut header, "channels", "chlist", &chlist);
+    write_attribute(
+        &mut header,
+        "compression",
+        "compression",
+        &[compression.code()],
+    );
+    write_attribute(&mut header, "dataWindow", "box2i", &window);
+    write_attribute(&mut header, "displayWindow", "box2i", &window);
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0]); // increasing y
+    write_attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1.0_f32.to_le_bytes(),
+    );
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    write_attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1.0_f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    let lines_per_block = compression.lines_per_block();
+    let mut chunks = Vec::new();
+    for y0 in (0..fb.height).step_by(lines_per_block as usize) {
+        let y1 = (y0 + lines_per_block).min(fb.height);
+        let raw = block_data(fb, y0, y1, pixel_type);
+        let data = match compression {
+            ExrCompression::None => raw,
+            ExrCompression::Zip => {
+                let packed = zlib::compress(&predict_and_interleave(&raw));
+                // readers treat a block as uncompressed when its size matches the raw size
+                if packed.len() < raw.len() {
+                    packed
+                } else {
+                    raw
+                }
+            }
+        };
+        chunks.push((y0, data));
+    }
+
+    // offset table: absolute file position of every chunk
+    let mut offset = (header.len() + 8 * chunks.len()) as u64;
+    out.write_all(&header)?;
+    for (_, data) in &chunks {
+        out.write_all(&offset.to_le_bytes())?;
+        offset += 8 + data.len() as u64;
+    }
+    for (y, data) in &chunks {
+        out.write_all(&(*y as i32).to_le_bytes())?;
+        out.write_all(&(data.len() as i32).to_le_bytes())?;
+        out.write_all(data)?;
+    }
+    Ok(())
+}
+
+fn write_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+// Scanlines y0..y1, each holding all B values, then all G, then all R.
+fn block_data(fb: &Framebuffer, y0: u32, y1: u32, pixel_type: ExrPixelType) -> Vec<u8> {
+    let mut data =
+        Vec::with_capacity((y1 - y0) as usize * fb.width as usize * 3 * pixel_type.size());
+    for y in y0..y1 {
+        for c in [2, 1, 0] {
+            for x in 0..fb.width {
+                let v = fb.get(x, y)[c] as f32;
+                match pixel_type {
+                    ExrPixelType::Half => data.extend_from_slice(&f32_to_half(v).to_le_bytes()),
+                    ExrPixelType::Float => data.extend_from_slice(&v.to_le_bytes()),
+                }
+            }
+        }
+    }
+    data
+}
+
+// ZIP pre-pass from the OpenEXR reference: split even/odd bytes into two halves, then
+// delta-encode so that smooth images compress well.
+fn predict_and_interleave(raw: &[u8]) -> Vec<u8> {
+    let half = raw.len().div_ceil(2);
+    let mut tmp = vec![0_u8; raw.len()];
+    for (i, &b) in raw.iter().enumerate() {
+        if i % 2 == 0 {
+            tmp[i / 2] = b;
+        } else {
+            tmp[half + i / 2] = b;
+        }
+    }
+    for i in (1..tmp.len()).rev() {
+        tmp[i] = tmp[i].wrapping_sub(tmp[i - 1]).wrapping_add(128);
+    }
+    tmp
+}
+
+// IEEE 754 binary16 with round-to-nearest-even; overflow becomes infinity.
+fn f32_to_half(v: f32) -> u16 {
+    let bits = v.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+
+    if exp == 0xff {
+        // infinity or NaN (keep NaN quiet and non-zero)
+        let nan = if mantissa != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let half_exp = exp - 127 + 15;
+    if half_exp >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if half_exp <= 0 {
+        // subnormal half or zero
+        if half_exp < -10 {
+            return sign;
+        }
+        let m = mantissa | 0x0080_0000;
+        let shift = (14 - half_exp) as u32;
+        let mut h = m >> shift;
+        let rem = m & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if rem > halfway || (rem == halfway && h & 1 == 1) {
+            h += 1;
+        }
+        return sign | h as u16;
+    }
+
+    let mut h = ((half_exp as u32) << 10) | (mantissa >> 13);
+    let rem = mantissa & 0x1fff;
+    if rem > 0x1000 || (rem == 0x1000 && h & 1 == 1) {
+        // may carry into the exponent, which is exactly the right result
+        h += 1;
+    }
+    sign | h as u16
+}
@@ -0,0 +1,47 @@
+use super::color::{Color, color_to_rgb8};
+use super::tonemap::ToneMapping;
+
+// Linear radiance per pixel, row-major from the top-left corner, and how many samples each
+// pixel averages.
+#[derive(Clone)]
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Color>,
+    pub samples: Vec<u32>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::default(); width as usize * height as usize],
+            samples: vec![0; width as usize * height as usize],
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    // Sample counts as gray levels, the busiest pixel at 1.0.
+    pub fn sample_map(&self) -> Framebuffer {
+        let max = self.samples.iter().copied().max().unwrap_or(0).max(1) as f64;
+        let mut map = Framebuffer::new(self.width, self.height);
+        for (p, &n) in map.pixels.iter_mut().zip(&self.samples) {
+            let v = n as f64 / max;
+            *p = Color::new(v, v, v);
+        }
+        map.samples.clone_from(&self.samples);
+        map
+    }
+
+    // Display-referred 8-bit RGB, for the LDR formats.
+    pub fn to_rgb8(&self, tone_mapping: &ToneMapping) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|c| color_to_rgb8(&tone_mapping.apply(c)))
+            .collect()
+    }
+}
@@ -0,0 +1,96 @@
+use std::io::Write;
+
+use super::framebuffer::Framebuffer;
+
+// Radiance RGBE (.hdr) writer with the standard per-scanline run-length encoding.
+
+pub fn write_hdr<W: Write>(out: &mut W, fb: &Framebuffer) -> std::io::Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        fb.height, fb.width
+    )?;
+
+    let width = fb.width as usize;
+    let mut scanline = vec![[0_u8; 4]; width];
+    let mut channel = vec![0_u8; width];
+    for y in 0..fb.height {
+        for (x, px) in scanline.iter_mut().enumerate() {
+            *px = to_rgbe(fb.get(x as u32, y).e);
+        }
+
+        // RLE is only defined for 8..=32767 pixel wide scanlines
+        if !(8..=0x7fff).contains(&width) {
+            for px in &scanline {
+                out.write_all(px)?;
+            }
+            continue;
+        }
+
+        out.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+        for c in 0..4 {
+            for (dst, px) in channel.iter_mut().zip(&scanline) {
+                *dst = px[c];
+            }
+            write_rle_channel(out, &channel)?;
+        }
+    }
+    Ok(())
+}
+
+fn to_rgbe(c: [f64; 3]) -> [u8; 4] {
+    let v = c[0].max(c[1]).max(c[2]);
+    if v.is_nan() || v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // v = m * 2^e with m in [0.5, 1)
+    let e = v.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2_f64.powi(e);
+    let byte = |x: f64| (x.max(0.0) * scale).min(255.0) as u8;
+    [
+        byte(c[0]),
+        byte(c[1]),
+        byte(c[2]),
+        (e + 128).clamp(0, 255) as u8,
+    ]
+}
+
+// Runs of 3..=127 equal bytes are written as (128 + n, value); everything else as
+// literal dumps of up to 128 bytes prefixed with their length.
+fn write_rle_channel<W: Write>(out: &mut W, data: &[u8]) -> std::io::Result<()> {
+    const MIN_RUN: usize = 3;
+    let mut i = 0;
+    while i < data.len() {
+        // find the next run long enough to be worth encoding
+        let mut run_start = i;
+        let mut run_len = 0;
+        while run_start < data.len() {
+            run_len = 1;
+            while run_start + run_len < data.len()
+                && run_len < 127
+                && data[run_start + run_len] == data[run_start]
+            {
+                run_len += 1;
+            }
+            if run_len >= MIN_RUN {
+                break;
+            }
+            run_start += run_len;
+        }
+        if run_len < MIN_RUN {
+            run_start = data.len();
+        }
+
+        while i < run_start {
+            let n = (run_start - i).min(128);
+            out.write_all(&[n as u8])?;
+            out.write_all(&data[i..i + n])?;
+            i += n;
+        }
+        if run_start < data.len() {
+            out.write_all(&[128 + run_len as u8, data[run_start]])?;
+            i = run_start + run_len;
+        }
+    }
+    Ok(())
+}
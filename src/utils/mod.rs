@@ -1,8 +1,25 @@
 pub(crate) mod aabb;
 pub(crate) mod bvh;
+pub(crate) mod cancel;
+pub(crate) mod checkpoint;
 pub(crate) mod color;
+pub(crate) mod exr;
+pub(crate) mod framebuffer;
+pub(crate) mod hdr;
 pub(crate) mod image;
 pub(crate) mod interval;
+pub(crate) mod json;
+pub(crate) mod mat4;
+pub(crate) mod obj;
+pub(crate) mod onb;
+pub(crate) mod output;
 pub(crate) mod perlin;
+pub(crate) mod png;
+pub(crate) mod poly;
+pub(crate) mod quat;
+pub(crate) mod sampler;
+pub(crate) mod tiles;
 pub(crate) mod timer;
+pub(crate) mod tonemap;
 pub(crate) mod vec3;
+pub(crate) mod zlib;
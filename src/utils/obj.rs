@@ -0,0 +1,442 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::core::hittable_list::HittableList;
+use crate::core::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::core::texture::{ImageTexture, SolidColor, Texture};
+use crate::core::triangle::TriangleMesh;
+use crate::utils::color::Color;
+use crate::utils::vec3::{Point3, Vec3};
+
+// Wavefront OBJ + MTL importer.
+// Supports v/vt/vn/f (polygons are fan-triangulated), g/o groups, usemtl and mtllib.
+// Each (group, material) pair becomes its own `TriangleMesh`.
+
+pub struct ObjMesh {
+    pub name: String,
+    pub mesh: Arc<TriangleMesh>,
+    pub emissive: bool,
+}
+
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub has_emitters: bool,
+}
+
+impl ObjModel {
+    // All faces of all meshes, ready to be fed into `BvhNode`.
+    pub fn triangles(&self) -> HittableList {
+        let mut list = HittableList::new();
+        for m in &self.meshes {
+            for tri in TriangleMesh::triangles(&m.mesh).objects {
+                list.add(tri);
+            }
+        }
+        list
+    }
+
+    // Faces of the emissive meshes, for light sampling.
+    pub fn lights(&self) -> HittableList {
+        let mut list = HittableList::new();
+        for m in self.meshes.iter().filter(|m| m.emissive) {
+            for tri in TriangleMesh::triangles(&m.mesh).objects {
+                list.add(tri);
+            }
+        }
+        list
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.meshes.iter().map(|m| m.mesh.face_count()).sum()
+    }
+}
+
+// Raw MTL statement values; converted to a `Material` once the whole block is read.
+#[derive(Clone)]
+struct MtlEntry {
+    kd: Color,
+    ks: Color,
+    ke: Color,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    illum: u32,
+    map_kd: Option<String>,
+}
+
+impl Default for MtlEntry {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::default(),
+            ke: Color::default(),
+            ns: 0.0,
+            ni: 1.5,
+            d: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+}
+
+// Vertices/faces of the group currently being read; OBJ indexes positions, UVs and normals
+// separately, so every distinct (v, vt, vn) triple becomes one mesh vertex.
+#[derive(Default)]
+struct MeshBuilder {
+    name: String,
+    material: String,
+    remap: HashMap<(usize, Option<usize>, Option<usize>), usize>,
+    positions: Vec<Point3>,
+    normals: Vec<Option<Vec3>>,
+    uvs: Vec<Option<(f64, f64)>>,
+    indices: Vec<[usize; 3]>,
+}
+
+struct ObjParser<'a> {
+    path: &'a Path,
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    default_material: Arc<dyn Material>,
+    emissive_materials: HashSet<String>,
+    meshes: Vec<ObjMesh>,
+    current: MeshBuilder,
+}
+
+pub fn load_obj(
+    path: impl AsRef<Path>,
+    default_material: Arc<dyn Material>,
+) -> io::Result<ObjModel> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+
+    let mut parser = ObjParser {
+        path,
+        positions: Vec::new(),
+        normals: Vec::new(),
+        uvs: Vec::new(),
+        materials: HashMap::new(),
+        textures: HashMap::new(),
+        default_material,
+        emissive_materials: HashSet::new(),
+        meshes: Vec::new(),
+        current: MeshBuilder::default(),
+    };
+
+    for (line_no, line) in source.lines().enumerate() {
+        parser
+            .parse_line(line)
+            .map_err(|msg| parse_error(path, line_no + 1, &msg))?;
+    }
+    parser.flush_mesh();
+
+    Ok(ObjModel {
+        has_emitters: parser.meshes.iter().any(|m| m.emissive),
+        meshes: parser.meshes,
+    })
+}
+
+impl ObjParser<'_> {
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let line = strip_comment(line);
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            return Ok(());
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(&args, 3, "v")?;
+                self.positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let v = parse_floats(&args, 3, "vn")?;
+                self.normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = parse_floats(&args, 1, "vt")?;
+                self.uvs.push((v[0], v.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => self.parse_face(&args)?,
+            "g" | "o" => {
+                let name = args.join(" ");
+                if name != self.current.name {
+                    self.flush_mesh();
+                    self.current.name = name;
+                }
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                if name != self.current.material {
+                    self.flush_mesh();
+                    self.current.material = name;
+                }
+            }
+            "mtllib" => {
+                for file in &args {
+                    let mtl_path = self.sibling_path(file);
+                    self.load_mtl(&mtl_path)?;
+                }
+            }
+            // smoothing groups, lines, points, curves, ...
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse_face(&mut self, args: &[&str]) -> Result<(), String> {
+        if args.len() < 3 {
+            return Err(format!(
+                "face needs at least 3 vertices, got {}",
+                args.len()
+            ));
+        }
+
+        let mut corners = Vec::with_capacity(args.len());
+        for arg in args {
+            let mut parts = arg.split('/');
+            let v = parts.next().unwrap_or("");
+            let vt = parts.next().filter(|s| !s.is_empty());
+            let vn = parts.next().filter(|s| !s.is_empty());
+
+            let v = resolve_index(v, self.positions.len(), "vertex")?;
+            let vt = vt
+                .map(|s| resolve_index(s, self.uvs.len(), "texture coordinate"))
+                .transpose()?;
+            let vn = vn
+                .map(|s| resolve_index(s, self.normals.len(), "normal"))
+                .transpose()?;
+            corners.push(self.mesh_vertex(v, vt, vn));
+        }
+
+        // fan triangulation around the first corner
+        for k in 1..corners.len() - 1 {
+            self.current
+                .indices
+                .push([corners[0], corners[k], corners[k + 1]]);
+        }
+        Ok(())
+    }
+
+    fn mesh_vertex(&mut self, v: usize, vt: Option<usize>, vn: Option<usize>) -> usize {
+        if let Some(&idx) = self.current.remap.get(&(v, vt, vn)) {
+            return idx;
+        }
+        let idx = self.current.positions.len();
+        self.current.positions.push(self.positions[v]);
+        self.current.uvs.push(vt.map(|i| self.uvs[i]));
+        self.current.normals.push(vn.map(|i| self.normals[i]));
+        self.current.remap.insert((v, vt, vn), idx);
+        idx
+    }
+
+    fn flush_mesh(&mut self) {
+        let name = self.current.name.clone();
+        let material_name = self.current.material.clone();
+        let builder = std::mem::take(&mut self.current);
+        self.current.name = name;
+        self.current.material = material_name;
+
+        if builder.indices.is_empty() {
+            return;
+        }
+
+        let material = match self.materials.get(&builder.material) {
+            Some(m) => m.clone(),
+            None => {
+                if !builder.material.is_empty() {
+                    eprintln!(
+                        "WARNING: {}: unknown material '{}', using default.",
+                        self.path.display(),
+                        builder.material
+                    );
+                }
+                self.default_material.clone()
+            }
+        };
+
+        // Attributes are all-or-nothing per mesh
+        let normals: Vec<Vec3> = builder.normals.iter().flatten().copied().collect();
+        let normals = if normals.len() == builder.positions.len() {
+            normals
+        } else {
+            Vec::new()
+        };
+        let uvs: Vec<(f64, f64)> = builder.uvs.iter().flatten().copied().collect();
+        let uvs = if uvs.len() == builder.positions.len() {
+            uvs
+        } else {
+            Vec::new()
+        };
+
+        let mesh = TriangleMesh::new(builder.positions, normals, uvs, builder.indices, material);
+        self.meshes.push(ObjMesh {
+            name: builder.name,
+            mesh,
+            emissive: self.emissive_materials.contains(&builder.material),
+        });
+    }
+
+    fn sibling_path(&self, file: &str) -> PathBuf {
+        match self.path.parent() {
+            Some(dir) => dir.join(file),
+            None => PathBuf::from(file),
+        }
+    }
+
+    fn load_mtl(&mut self, path: &Path) -> Result<(), String> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("WARNING: could not read '{}': {e}", path.display());
+                return Ok(());
+            }
+        };
+
+        let mut current: Option<(String, MtlEntry)> = None;
+        for (line_no, line) in source.lines().enumerate() {
+            let line = strip_comment(line);
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+            let args: Vec<&str> = tokens.collect();
+            let err = |msg: String| format!("{}:{}: {msg}", path.display(), line_no + 1);
+
+            if keyword == "newmtl" {
+                if let Some((name, entry)) = current.take() {
+                    self.add_material(path, name, &entry);
+                }
+                current = Some((args.join(" "), MtlEntry::default()));
+                continue;
+            }
+
+            let Some((_, entry)) = current.as_mut() else {
+                continue;
+            };
+            match keyword {
+                "Kd" => entry.kd = parse_color(&args, keyword).map_err(err)?,
+                "Ks" => entry.ks = parse_color(&args, keyword).map_err(err)?,
+                "Ke" => entry.ke = parse_color(&args, keyword).map_err(err)?,
+                "Ns" => entry.ns = parse_floats(&args, 1, keyword).map_err(err)?[0],
+                "Ni" => entry.ni = parse_floats(&args, 1, keyword).map_err(err)?[0],
+                "d" => entry.d = parse_floats(&args, 1, keyword).map_err(err)?[0],
+                "Tr" => entry.d = 1.0 - parse_floats(&args, 1, keyword).map_err(err)?[0],
+                "illum" => {
+                    entry.illum = args
+                        .first()
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| err("illum expects an integer".to_string()))?;
+                }
+                // options such as `-s 1 1 1` precede the file name
+                "map_Kd" => entry.map_kd = args.last().map(|s| s.to_string()),
+                _ => {}
+            }
+        }
+        if let Some((name, entry)) = current.take() {
+            self.add_material(path, name, &entry);
+        }
+
+        Ok(())
+    }
+
+    fn add_material(&mut self, mtl_path: &Path, name: String, entry: &MtlEntry) {
+        let max_component = |c: &Color| c.x().max(c.y()).max(c.z());
+
+        let material: Arc<dyn Material> = if max_component(&entry.ke) > 0.0 {
+            self.emissive_materials.insert(name.clone());
+            Arc::new(DiffuseLight::new_color(entry.ke))
+        } else if entry.d < 1.0 || matches!(entry.illum, 4 | 6 | 7) {
+            Arc::new(Dielectric::new(entry.ni))
+        } else if entry.illum == 3 || max_component(&entry.ks) > max_component(&entry.kd) {
+            // Phong exponent -> fuzz, roughly matching lobe widths
+            let fuzz = (2.0 / (entry.ns + 2.0)).sqrt();
+            Arc::new(Metal::new(entry.ks, fuzz))
+        } else {
+            let tex: Arc<dyn Texture> = match &entry.map_kd {
+                Some(file) => self.texture(mtl_path, file),
+                None => Arc::new(SolidColor::new(entry.kd)),
+            };
+            Arc::new(Lambertian::new(tex))
+        };
+
+        self.materials.insert(name, material);
+    }
+
+    fn texture(&mut self, mtl_path: &Path, file: &str) -> Arc<dyn Texture> {
+        if let Some(tex) = self.textures.get(file) {
+            return tex.clone();
+        }
+
+        // Prefer a file next to the .mtl, otherwise fall back to the usual image search path
+        let local = mtl_path.parent().map(|dir| dir.join(file));
+        let tex: Arc<dyn Texture> = match local {
+            Some(p) if p.exists() => Arc::new(ImageTexture::new(p.to_string_lossy())),
+            _ => Arc::new(ImageTexture::new(file)),
+        };
+        self.textures.insert(file.to_string(), tex.clone());
+        tex
+    }
+}
+
+fn parse_error(path: &Path, line: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}:{line}: {msg}", path.display()),
+    )
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+fn parse_floats(args: &[&str], min_count: usize, keyword: &str) -> Result<Vec<f64>, String> {
+    if args.len() < min_count {
+        return Err(format!(
+            "'{keyword}' expects at least {min_count} numbers, got {}",
+            args.len()
+        ));
+    }
+    args.iter()
+        .map(|s| {
+            s.parse::<f64>()
+                .map_err(|_| format!("'{keyword}': invalid number '{s}'"))
+        })
+        .collect()
+}
+
+fn parse_color(args: &[&str], keyword: &str) -> Result<Color, String> {
+    // `Kd r` is shorthand for `Kd r r r`
+    let v = parse_floats(args, 1, keyword)?;
+    if v.len() < 3 {
+        return Ok(Color::new(v[0], v[0], v[0]));
+    }
+    Ok(Color::new(v[0], v[1], v[2]))
+}
+
+// OBJ indices are 1-based; negative values count back from the most recent element.
+fn resolve_index(s: &str, count: usize, what: &str) -> Result<usize, String> {
+    let i: i64 = s
+        .parse()
+        .map_err(|_| format!("invalid {what} index '{s}'"))?;
+    let idx = if i > 0 {
+        i - 1
+    } else if i < 0 {
+        count as i64 + i
+    } else {
+        -1
+    };
+    if idx < 0 || idx >= count as i64 {
+        return Err(format!("{what} index {i} out of range (have {count})"));
+    }
+    Ok(idx as usize)
+}
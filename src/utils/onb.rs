@@ -0,0 +1,40 @@
+use super::vec3::Vec3;
+
+// Orthonormal basis with `w` along a given direction, used to turn samples generated
+// around +z into world space.
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).unit_vector();
+        let u = w.cross(&v);
+        Self { u, v, w }
+    }
+
+    // `w` along `n` and `u` along the part of `tangent` perpendicular to it, falling back
+    // to `new` when that part vanishes.
+    pub fn from_tangent(n: &Vec3, tangent: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let t = tangent - w * w.dot(tangent);
+        if t.length_squared() <= 1e-12 * tangent.length_squared() {
+            return Self::new(n);
+        }
+        let u = t.unit_vector();
+        let v = w.cross(&u);
+        Self { u, v, w }
+    }
+
+    pub fn transform(&self, v: &Vec3) -> Vec3 {
+        v.x() * self.u + v.y() * self.v + v.z() * self.w
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::exr::{ExrCompression, ExrPixelType, write_exr};
+use super::framebuffer::Framebuffer;
+use super::hdr::write_hdr;
+use super::png::write_png;
+use super::tonemap::ToneMapping;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Ppm, // ASCII P3
+    Png,
+    Hdr, // Radiance RGBE
+    Exr,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("ppm") => Ok(ImageFormat::Ppm),
+            Some("png") => Ok(ImageFormat::Png),
+            Some("hdr") => Ok(ImageFormat::Hdr),
+            Some("exr") => Ok(ImageFormat::Exr),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "unsupported output format for '{}' (expected .ppm, .png, .hdr or .exr)",
+                    path.display()
+                ),
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OutputOptions {
+    pub tone_mapping: ToneMapping, // LDR formats only, HDR files keep linear radiance
+    pub exr_pixel_type: ExrPixelType,
+    pub exr_compression: ExrCompression,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            tone_mapping: ToneMapping::default(),
+            exr_pixel_type: ExrPixelType::Half,
+            exr_compression: ExrCompression::Zip,
+        }
+    }
+}
+
+// Writes the framebuffer, picking the format from the file extension.
+pub fn save_image(
+    path: impl AsRef<Path>,
+    fb: &Framebuffer,
+    options: &OutputOptions,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path)?;
+    let mut out = BufWriter::new(File::create(path)?);
+
+    match format {
+        ImageFormat::Ppm => {
+            writeln!(out, "P3\n{} {}\n255", fb.width, fb.height)?;
+            for px in fb.to_rgb8(&options.tone_mapping).chunks_exact(3) {
+                writeln!(out, "{} {} {}", px[0], px[1], px[2])?;
+            }
+        }
+        ImageFormat::Png => write_png(
+            &mut out,
+            fb.width,
+            fb.height,
+            &fb.to_rgb8(&options.tone_mapping),
+        )?,
+        ImageFormat::Hdr => write_hdr(&mut out, fb)?,
+        ImageFormat::Exr => write_exr(
+            &mut out,
+            fb,
+            options.exr_pixel_type,
+            options.exr_compression,
+        )?,
+    }
+
+    out.flush()
+}
@@ -1,3 +1,4 @@
+use crate::utils::sampler::IndependentSampler;
 use crate::utils::vec3::{Point3, Vec3};
 
 pub struct Perlin {
@@ -10,18 +11,20 @@ pub struct Perlin {
 impl Perlin {
     const POINT_COUNT: usize = 256;
 
-    pub fn new() -> Self {
+    // The same seed always produces the same noise field.
+    pub fn new(seed: u64) -> Self {
+        let mut sampler = IndependentSampler::new(seed);
         let mut randvec = [Vec3::new(0.0, 0.0, 0.0); Self::POINT_COUNT];
         for i in 0..Self::POINT_COUNT {
-            randvec[i] = Vec3::random(-1.0, 1.0).unit_vector();
+            randvec[i] = Vec3::random(&mut sampler, -1.0, 1.0).unit_vector();
         }
 
         let mut perm_x = [0_u32; Self::POINT_COUNT];
         let mut perm_y = [0_u32; Self::POINT_COUNT];
         let mut perm_z = [0_u32; Self::POINT_COUNT];
-        Self::perlin_generate_perm(&mut perm_x);
-        Self::perlin_generate_perm(&mut perm_y);
-        Self::perlin_generate_perm(&mut perm_z);
+        Self::perlin_generate_perm(&mut sampler, &mut perm_x);
+        Self::perlin_generate_perm(&mut sampler, &mut perm_y);
+        Self::perlin_generate_perm(&mut sampler, &mut perm_z);
 
         Self {
             randvec,
@@ -70,17 +73,21 @@ impl Perlin {
         return accum.abs();
     }
 
-    fn perlin_generate_perm(p: &mut [u32; Self::POINT_COUNT]) {
+    fn perlin_generate_perm(sampler: &mut IndependentSampler, p: &mut [u32; Self::POINT_COUNT]) {
         for i in 0..Self::POINT_COUNT {
             p[i] = i as u32;
         }
 
-        Self::shuffle_in_place(p, Self::POINT_COUNT);
+        Self::shuffle_in_place(sampler, p, Self::POINT_COUNT);
     }
 
-    fn shuffle_in_place(p: &mut [u32; Self::POINT_COUNT], n: usize) {
+    fn shuffle_in_place(
+        sampler: &mut IndependentSampler,
+        p: &mut [u32; Self::POINT_COUNT],
+        n: usize,
+    ) {
         for i in (0..n).rev() {
-            let target = rand::random::<u32>() % (i as u32 + 1);
+            let target = sampler.next_u32() % (i as u32 + 1);
             p.swap(i, target as usize);
         }
     }
@@ -0,0 +1,94 @@
+use std::io::Write;
+
+use super::zlib;
+
+// Minimal PNG encoder: 8-bit RGB, one IDAT chunk. Each scanline picks the filter whose output
+// has the smallest sum of absolute (signed) bytes, the usual heuristic from libpng.
+
+pub fn write_png<W: Write>(
+    out: &mut W,
+    width: u32,
+    height: u32,
+    rgb: &[u8],
+) -> std::io::Result<()> {
+    const BPP: usize = 3;
+    let stride = width as usize * BPP;
+    assert_eq!(rgb.len(), stride * height as usize);
+
+    let mut filtered = Vec::with_capacity((stride + 1) * height as usize);
+    let zero_row = vec![0_u8; stride];
+    let mut candidate = vec![0_u8; stride];
+    let mut best = vec![0_u8; stride];
+
+    for y in 0..height as usize {
+        let row = &rgb[y * stride..(y + 1) * stride];
+        let prior = if y == 0 {
+            &zero_row[..]
+        } else {
+            &rgb[(y - 1) * stride..y * stride]
+        };
+
+        let mut best_filter = 0;
+        let mut best_score = u64::MAX;
+        for filter in 0..5_u8 {
+            for i in 0..stride {
+                let a = if i >= BPP { row[i - BPP] } else { 0 };
+                let b = prior[i];
+                let c = if i >= BPP { prior[i - BPP] } else { 0 };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                candidate[i] = row[i].wrapping_sub(predicted);
+            }
+            let score: u64 = candidate
+                .iter()
+                .map(|&v| (v as i8).unsigned_abs() as u64)
+                .sum();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+
+        filtered.push(best_filter);
+        filtered.extend_from_slice(&best);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // bit depth 8, color type 2 (RGB), deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'])?;
+    write_chunk(out, b"IHDR", &ihdr)?;
+    write_chunk(out, b"IDAT", &zlib::compress(&filtered))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = zlib::crc32_update(zlib::crc32(kind), data);
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
@@ -0,0 +1,98 @@
+// Real roots of polynomials, for implicit surfaces. Closed-form quartic and cubic formulas
+// cancel badly near double roots, which is exactly where rays graze a surface, so this
+// brackets instead: the roots of a polynomial are separated by the roots of its
+// derivative, found the same way, and between two neighbouring ones the polynomial is
+// monotonic, so a sign change there brackets exactly one root. Each is then polished by
+// Newton's method kept inside its bracket by bisection.
+
+// Sum of coeffs[i] * x^i.
+pub fn eval(coeffs: &[f64], x: f64) -> f64 {
+    coeffs.iter().rev().fold(0.0, |acc, &c| acc * x + c)
+}
+
+// Roots of sum of coeffs[i] * x^i in [lo, hi], in increasing order. Roots of even
+// multiplicity, where the polynomial touches zero without crossing, may be missed.
+pub fn roots_in(coeffs: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    let degree = match coeffs.iter().rposition(|&c| c != 0.0) {
+        Some(d) if lo <= hi => d,
+        _ => return Vec::new(),
+    };
+    let coeffs = &coeffs[..=degree];
+    match degree {
+        0 => Vec::new(),
+        1 => {
+            let x = -coeffs[0] / coeffs[1];
+            if (lo..=hi).contains(&x) {
+                vec![x]
+            } else {
+                Vec::new()
+            }
+        }
+        _ => {
+            let derivative: Vec<f64> = coeffs
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &c)| i as f64 * c)
+                .collect();
+            let mut ends = vec![lo];
+            ends.extend(roots_in(&derivative, lo, hi));
+            ends.push(hi);
+
+            let mut roots = Vec::new();
+            for pair in ends.windows(2) {
+                if let Some(x) = bracketed_root(coeffs, &derivative, pair[0], pair[1]) {
+                    // a root on a shared end shows up in both neighbouring brackets
+                    if roots.last() != Some(&x) {
+                        roots.push(x);
+                    }
+                }
+            }
+            roots
+        }
+    }
+}
+
+// The root in [a, b] if the polynomial changes sign there.
+fn bracketed_root(coeffs: &[f64], derivative: &[f64], mut a: f64, mut b: f64) -> Option<f64> {
+    let fa = eval(coeffs, a);
+    let fb = eval(coeffs, b);
+    if fa == 0.0 {
+        return Some(a);
+    }
+    if fb == 0.0 {
+        return Some(b);
+    }
+    if (fa < 0.0) == (fb < 0.0) {
+        return None;
+    }
+    let rising = fa < 0.0;
+
+    let mut x = 0.5 * (a + b);
+    for _ in 0..100 {
+        let fx = eval(coeffs, x);
+        if fx == 0.0 {
+            return Some(x);
+        }
+        if (fx < 0.0) == rising {
+            a = x;
+        } else {
+            b = x;
+        }
+        if b - a <= 1e-14 * (1.0 + x.abs()) {
+            break;
+        }
+        let step = fx / eval(derivative, x);
+        let newton = x - step;
+        // bisect when Newton leaves the bracket or is making little headway
+        if newton > a && newton < b && step.abs() < 0.5 * (b - a) {
+            x = newton;
+            if step.abs() <= 1e-14 * (1.0 + x.abs()) {
+                break;
+            }
+        } else {
+            x = 0.5 * (a + b);
+        }
+    }
+    Some(x)
+}
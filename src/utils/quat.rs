@@ -0,0 +1,106 @@
+use super::mat4::Mat4;
+use super::vec3::Vec3;
+
+// Unit quaternion w + v for rotations.
+#[derive(Clone, Copy)]
+pub struct Quat {
+    pub w: f64,
+    pub v: Vec3,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quat {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            v: Vec3::default(),
+        }
+    }
+
+    // Right-handed rotation about `axis` (need not be unit length), as `Mat4::rotation`.
+    pub fn from_axis_angle(axis: &Vec3, angle_degrees: f64) -> Self {
+        let (sin, cos) = (angle_degrees.to_radians() / 2.0).sin_cos();
+        Self {
+            w: cos,
+            v: axis.unit_vector() * sin,
+        }
+    }
+
+    // The inverse rotation.
+    pub fn conjugate(&self) -> Quat {
+        Quat {
+            w: self.w,
+            v: -self.v,
+        }
+    }
+
+    pub fn dot(&self, other: &Quat) -> f64 {
+        self.w * other.w + self.v.dot(&other.v)
+    }
+
+    // Constant angular speed from `self` at t = 0 to `other` at t = 1, the short way round;
+    // keyframes more than half a turn apart need one in between.
+    pub fn slerp(&self, other: &Quat, t: f64) -> Quat {
+        let mut cos = self.dot(other);
+        let mut other = *other;
+        if cos < 0.0 {
+            cos = -cos;
+            other = Quat {
+                w: -other.w,
+                v: -other.v,
+            };
+        }
+        // nearly parallel: the weights below lose precision, and lerp is just as good
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        let q = Quat {
+            w: a * self.w + b * other.w,
+            v: self.v * a + other.v * b,
+        };
+        let len = q.dot(&q).sqrt();
+        Quat {
+            w: q.w / len,
+            v: q.v / len,
+        }
+    }
+
+    // Angle in radians between the rotations, the arc `slerp` sweeps.
+    pub fn angle_to(&self, other: &Quat) -> f64 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    pub fn to_mat4(self) -> Mat4 {
+        let (w, x, y, z) = (self.w, self.v.x(), self.v.y(), self.v.z());
+        Mat4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
@@ -0,0 +1,624 @@
+// Random numbers for rendering. Every camera sample starts a fresh sequence keyed by
+// (seed, pixel, sample index), so a render is reproducible no matter how rayon schedules the
+// tiles. Samplers hand out numbers by dimension: the camera and integrator jump to a fixed
+// dimension before each use (pixel, lens, time, then per bounce light and BSDF), so a
+// low-discrepancy pattern always feeds the same decision.
+
+pub trait Sampler {
+    // Starts sample `index` of pixel (x, y) at dimension 0.
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32);
+
+    // Continues the current sample at dimension `dim`.
+    fn set_dimension(&mut self, dim: u32);
+
+    // Uniform in [0, 1); consumes one dimension.
+    fn get_1d(&mut self) -> f64;
+
+    // Uniform in [0, 1)^2; consumes two dimensions that are stratified together.
+    fn get_2d(&mut self) -> (f64, f64);
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.get_1d()
+    }
+
+    // Uniform in 0..n.
+    fn index(&mut self, n: usize) -> usize {
+        ((self.get_1d() * n as f64) as usize).min(n - 1)
+    }
+}
+
+// Which sampler a render uses: plain random, jittered strata or a low-discrepancy set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol, // Owen-scrambled
+    Cmj,   // correlated multi-jittered
+}
+
+impl SamplerKind {
+    pub const NAMES: &'static str = "independent, stratified, halton, sobol, cmj";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "independent" => Some(SamplerKind::Independent),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            "cmj" => Some(SamplerKind::Cmj),
+            _ => None,
+        }
+    }
+
+    // Stratified and CMJ patterns are laid out for `samples_per_pixel` samples; indices past
+    // it start another, independently scrambled, pattern.
+    pub fn create(self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        let spp = samples_per_pixel.max(1);
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, spp)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+            SamplerKind::Cmj => Box::new(CmjSampler::new(seed, spp)),
+        }
+    }
+}
+
+// Sample position shared by the pattern samplers: which pixel sample and which dimension.
+#[derive(Default)]
+struct SampleState {
+    pixel_hash: u64,
+    index: u32,
+    dim: u32,
+}
+
+impl SampleState {
+    fn start(&mut self, seed: u64, x: u32, y: u32, index: u32) {
+        self.pixel_hash = hash(hash(seed, (x as u64) << 32 | y as u64), 0);
+        self.index = index;
+        self.dim = 0;
+    }
+
+    // Scramble seed for the dimension about to be used, then advances past `count` of them.
+    fn take(&mut self, count: u32) -> u32 {
+        let h = hash(self.pixel_hash, self.dim as u64) as u32;
+        self.dim += count;
+        h
+    }
+}
+
+// PCG32, one stream per camera sample. Also used on its own wherever plain random numbers
+// are wanted (scene layouts, noise tables).
+pub struct IndependentSampler {
+    seed: u64,
+    state: u64,
+    inc: u64,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        let mut sampler = Self {
+            seed,
+            state: 0,
+            inc: 1,
+        };
+        sampler.reseed(seed);
+        sampler
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.state = 0;
+        self.inc = (splitmix64(seed) << 1) | 1;
+        self.next_u32();
+        self.state = self.state.wrapping_add(seed);
+        self.next_u32();
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(6364136223846793005).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+}
+
+impl Sampler for IndependentSampler {
+    // nearby pixels and sample indices are decorrelated by hashing rather than by stepping
+    // a shared generator
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        let h = hash(hash(self.seed, (x as u64) << 32 | y as u64), index as u64);
+        self.reseed(h);
+    }
+
+    // every dimension is as good as any other
+    fn set_dimension(&mut self, _dim: u32) {}
+
+    fn get_1d(&mut self) -> f64 {
+        // 53 random bits from two draws
+        let hi = (self.next_u32() as u64) << 21;
+        let lo = (self.next_u32() >> 11) as u64;
+        (hi | lo) as f64 * (1.0 / (1_u64 << 53) as f64)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+// Lends `inner` for draws whose count is not known in advance, such as one per medium a
+// ray crosses. The first `dims - 1` come from `inner` at the dimensions from its current
+// one on; the last of them seeds a hashed stream for any beyond, so a run of draws never
+// spills into dimensions reserved for other decisions.
+pub struct BoundedSampler<'a> {
+    inner: &'a mut dyn Sampler,
+    left: u32,
+    overflow: Option<IndependentSampler>,
+}
+
+impl<'a> BoundedSampler<'a> {
+    pub fn new(inner: &'a mut dyn Sampler, dims: u32) -> Self {
+        Self {
+            inner,
+            left: dims.max(1),
+            overflow: None,
+        }
+    }
+}
+
+impl Sampler for BoundedSampler<'_> {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.inner.start_pixel_sample(x, y, index);
+    }
+
+    fn set_dimension(&mut self, dim: u32) {
+        self.inner.set_dimension(dim);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        if self.left > 1 {
+            self.left -= 1;
+            return self.inner.get_1d();
+        }
+        let inner = &mut self.inner;
+        self.overflow
+            .get_or_insert_with(|| {
+                IndependentSampler::new((inner.get_1d() * (1_u64 << 53) as f64) as u64)
+            })
+            .get_1d()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+// Jittered strata: n strata in 1D and a near-square grid in 2D, visited in a different
+// random order per pixel and dimension so that dimensions do not correlate.
+pub struct StratifiedSampler {
+    seed: u64,
+    spp: u32,
+    state: SampleState,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        Self {
+            seed,
+            spp: samples_per_pixel,
+            state: SampleState::default(),
+        }
+    }
+
+    // Position within the current pattern and a scramble seed unique to the pattern.
+    fn pattern(&mut self, count: u32) -> (u32, u32) {
+        let p = self.state.take(count);
+        let round = self.state.index / self.spp;
+        (self.state.index % self.spp, mix32(p, round))
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(self.seed, x, y, index);
+    }
+
+    fn set_dimension(&mut self, dim: u32) {
+        self.state.dim = dim;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (s, p) = self.pattern(1);
+        let stratum = permute(s, self.spp, p);
+        (stratum as f64 + rand_unit(s, p.wrapping_mul(0xa399d265))) / self.spp as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (s, p) = self.pattern(2);
+        let nx = (self.spp as f64).sqrt() as u32;
+        let ny = self.spp.div_ceil(nx);
+        let stratum = permute(s, nx * ny, p);
+        let jx = rand_unit(s, p.wrapping_mul(0xa399d265));
+        let jy = rand_unit(s, p.wrapping_mul(0x711ad6a5));
+        (
+            ((stratum % nx) as f64 + jx) / nx as f64,
+            ((stratum / nx) as f64 + jy) / ny as f64,
+        )
+    }
+}
+
+// Correlated multi-jittered sampling (Kensler 2013): 2D samples are stratified both on the
+// grid and in each 1D projection.
+pub struct CmjSampler {
+    seed: u64,
+    spp: u32,
+    state: SampleState,
+}
+
+impl CmjSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        Self {
+            seed,
+            spp: samples_per_pixel,
+            state: SampleState::default(),
+        }
+    }
+
+    fn pattern(&mut self, count: u32) -> (u32, u32) {
+        let p = self.state.take(count);
+        let round = self.state.index / self.spp;
+        let p = mix32(p, round);
+        // shuffle the sample order too, or every dimension would walk the grid alike
+        (
+            permute(
+                self.state.index % self.spp,
+                self.spp,
+                p.wrapping_mul(0x51633e2d),
+            ),
+            p,
+        )
+    }
+}
+
+impl Sampler for CmjSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(self.seed, x, y, index);
+    }
+
+    fn set_dimension(&mut self, dim: u32) {
+        self.state.dim = dim;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (s, p) = self.pattern(1);
+        (s as f64 + rand_unit(s, p.wrapping_mul(0xa399d265))) / self.spp as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (s, p) = self.pattern(2);
+        let m = ((self.spp as f64).sqrt() as u32).max(1);
+        let n = self.spp.div_ceil(m);
+        let sx = permute(s % m, m, p.wrapping_mul(0xa511e9b3));
+        let sy = permute(s / m, n, p.wrapping_mul(0x63d83595));
+        let jx = rand_unit(s, p.wrapping_mul(0xa399d265));
+        let jy = rand_unit(s, p.wrapping_mul(0x711ad6a5));
+        (
+            ((s % m) as f64 + (sy as f64 + jx) / n as f64) / m as f64,
+            ((s / m) as f64 + (sx as f64 + jy) / m as f64) / n as f64,
+        )
+    }
+}
+
+// Halton sequence with one prime base per dimension, Owen-scrambled per pixel. A plain
+// random shift is not enough: with few samples the large bases all start out as i / p and
+// neighbouring dimensions line up. Dimensions past the prime table fall back to hashed random
+// numbers.
+pub struct HaltonSampler {
+    seed: u64,
+    state: SampleState,
+}
+
+const HALTON_DIMS: usize = 256;
+const PRIMES: [u32; HALTON_DIMS] = first_primes();
+
+const fn first_primes() -> [u32; HALTON_DIMS] {
+    let mut primes = [0_u32; HALTON_DIMS];
+    let mut count = 0;
+    let mut candidate = 2;
+    while count < HALTON_DIMS {
+        let mut k = 0;
+        let mut is_prime = true;
+        while k < count && primes[k] * primes[k] <= candidate {
+            if candidate % primes[k] == 0 {
+                is_prime = false;
+                break;
+            }
+            k += 1;
+        }
+        if is_prime {
+            primes[count] = candidate;
+            count += 1;
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+// Radical inverse of `index` with every digit permuted depending on the digits before it,
+// continued past the last nonzero digit until the result is exact in 32 bits.
+fn owen_radical_inverse(base: u32, mut index: u32, seed: u32) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv = inv_base;
+    let mut result = 0.0;
+    let mut prefix = seed;
+    while inv > 1.0 / (1_u64 << 32) as f64 {
+        let digit = index % base;
+        index /= base;
+        result += permute(digit, base, prefix) as f64 * inv;
+        prefix = mix32(prefix, digit);
+        inv *= inv_base;
+    }
+    result
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: SampleState::default(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(self.seed, x, y, index);
+    }
+
+    fn set_dimension(&mut self, dim: u32) {
+        self.state.dim = dim;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dim = self.state.dim as usize;
+        let p = self.state.take(1);
+        if dim >= HALTON_DIMS {
+            return rand_unit(self.state.index, p);
+        }
+        owen_radical_inverse(PRIMES[dim], self.state.index, p)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+// The first two Sobol dimensions, Owen-scrambled per pixel and per dimension pair, with the
+// sample order shuffled per pair as well (Burley 2020). Those two dimensions are stratified
+// at every power of two, and the shuffling keeps pairs from correlating with each other.
+pub struct SobolSampler {
+    seed: u64,
+    state: SampleState,
+}
+
+const SOBOL_MATRIX_1: [u32; 32] = sobol_matrix_1();
+
+// Direction numbers of the second dimension, primitive polynomial x + 1: m_k = m_{k-1} xor
+// (m_{k-1} << 1), stored as 32-bit fractions.
+const fn sobol_matrix_1() -> [u32; 32] {
+    let mut v = [0_u32; 32];
+    let mut m: u64 = 1;
+    let mut k = 0;
+    while k < 32 {
+        v[k] = (m << (31 - k)) as u32;
+        m ^= m << 1;
+        k += 1;
+    }
+    v
+}
+
+fn sobol(index: u32, dim: u32) -> u32 {
+    if dim == 0 {
+        return index.reverse_bits();
+    }
+    let mut result = 0;
+    let mut i = index;
+    let mut k = 0;
+    while i != 0 {
+        if i & 1 != 0 {
+            result ^= SOBOL_MATRIX_1[k];
+        }
+        i >>= 1;
+        k += 1;
+    }
+    result
+}
+
+// Laine-Karras style hash permutation; applied to reversed bits it is a nested uniform
+// (Owen) scramble, where each bit is flipped depending only on the bits above it.
+fn owen_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x.reverse_bits()
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: SampleState::default(),
+        }
+    }
+
+    fn sample(&mut self, count: u32) -> (f64, f64) {
+        let p = self.state.take(count);
+        let index = owen_scramble(self.state.index, p);
+        let x = owen_scramble(sobol(index, 0), mix32(p, 1));
+        let y = owen_scramble(sobol(index, 1), mix32(p, 2));
+        (to_unit(x), to_unit(y))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(self.seed, x, y, index);
+    }
+
+    fn set_dimension(&mut self, dim: u32) {
+        self.state.dim = dim;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.sample(1).0
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        self.sample(2)
+    }
+}
+
+// Kensler's hashed permutation of 0..n, a bijection chosen by `p`.
+fn permute(mut i: u32, n: u32, p: u32) -> u32 {
+    if n <= 1 {
+        return 0;
+    }
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    // cycle-walk until the value lands inside 0..n
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
+        }
+    }
+    (i.wrapping_add(p)) % n
+}
+
+// Hash of (i, p) mapped to [0, 1).
+fn rand_unit(i: u32, p: u32) -> f64 {
+    to_unit(hash(p as u64, i as u64) as u32)
+}
+
+fn to_unit(x: u32) -> f64 {
+    x as f64 * (1.0 / (1_u64 << 32) as f64)
+}
+
+fn mix32(a: u32, b: u32) -> u32 {
+    hash(a as u64, b as u64) as u32
+}
+
+fn hash(a: u64, b: u64) -> u64 {
+    splitmix64(splitmix64(a) ^ b)
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [SamplerKind; 5] = [
+        SamplerKind::Independent,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+        SamplerKind::Cmj,
+    ];
+
+    // RMSE over `pixels` independent estimates of the integral of `f` over the unit square,
+    // each from `spp` samples at dimension `dim`.
+    fn rmse(kind: SamplerKind, dim: u32, f: impl Fn(f64, f64) -> f64, exact: f64) -> f64 {
+        let (pixels, spp) = (256, 64);
+        let mut sampler = kind.create(7, spp);
+        let mut sum_sq = 0.0;
+        for pixel in 0..pixels {
+            let mut estimate = 0.0;
+            for index in 0..spp {
+                sampler.start_pixel_sample(pixel % 16, pixel / 16, index);
+                sampler.set_dimension(dim);
+                let (x, y) = sampler.get_2d();
+                estimate += f(x, y);
+            }
+            let err = estimate / spp as f64 - exact;
+            sum_sq += err * err;
+        }
+        (sum_sq / pixels as f64).sqrt()
+    }
+
+    fn check_beats_independent(dim: u32, f: impl Fn(f64, f64) -> f64 + Copy, exact: f64) {
+        let independent = rmse(SamplerKind::Independent, dim, f, exact);
+        for kind in &KINDS[1..] {
+            let err = rmse(*kind, dim, f, exact);
+            assert!(
+                err <= independent,
+                "{kind:?} at dimension {dim}: rmse {err} > independent {independent}"
+            );
+        }
+    }
+
+    #[test]
+    fn smooth_integrand_converges_faster_than_independent() {
+        for dim in [0, 2, 5, 17] {
+            check_beats_independent(dim, |x, y| (x + y) * (x + y), 7.0 / 6.0);
+        }
+    }
+
+    #[test]
+    fn discontinuous_integrand_converges_faster_than_independent() {
+        let quarter_disk = |x: f64, y: f64| if x * x + y * y < 1.0 { 1.0 } else { 0.0 };
+        for dim in [0, 2, 5, 17] {
+            check_beats_independent(dim, quarter_disk, std::f64::consts::FRAC_PI_4);
+        }
+    }
+
+    #[test]
+    fn values_are_in_unit_interval() {
+        for kind in KINDS {
+            for spp in [1, 7, 16] {
+                let mut sampler = kind.create(3, spp);
+                for index in 0..2 * spp {
+                    sampler.start_pixel_sample(5, 9, index);
+                    for dim in 0..300 {
+                        sampler.set_dimension(dim);
+                        let x = sampler.get_1d();
+                        let (y, z) = sampler.get_2d();
+                        for v in [x, y, z] {
+                            assert!((0.0..1.0).contains(&v), "{kind:?} gave {v}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
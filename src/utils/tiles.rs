@@ -0,0 +1,95 @@
+// Splits the image into square tiles and orders them for rendering. Both orders keep
+// consecutive tiles close together, so the part of the image that fills in first is a
+// coherent region rather than a scatter of rows.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileOrder {
+    Spiral,  // outward from the center, where the subject usually is
+    Hilbert, // along a Hilbert curve, the most cache friendly
+}
+
+impl TileOrder {
+    pub const NAMES: &'static str = "spiral, hilbert";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "spiral" => Some(TileOrder::Spiral),
+            "hilbert" => Some(TileOrder::Hilbert),
+            _ => None,
+        }
+    }
+}
+
+// Pixels x0..x1, y0..y1; tiles on the right and bottom edges may be smaller.
+#[derive(Clone, Copy, Debug)]
+pub struct Tile {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Tile {
+    // Pixel coordinates, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + use<> {
+        let (x0, x1) = (self.x0, self.x1);
+        (self.y0..self.y1).flat_map(move |j| (x0..x1).map(move |i| (i, j)))
+    }
+}
+
+pub fn tiles(width: u32, height: u32, size: u32, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let nx = width.div_ceil(size);
+    let ny = height.div_ceil(size);
+    let mut grid: Vec<(u32, u32)> = (0..ny)
+        .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+        .collect();
+
+    match order {
+        TileOrder::Spiral => {
+            let cx = (nx as f64 - 1.0) / 2.0;
+            let cy = (ny as f64 - 1.0) / 2.0;
+            // ring by ring, each ring walked by angle
+            let key = |&(tx, ty): &(u32, u32)| {
+                let dx = tx as f64 - cx;
+                let dy = ty as f64 - cy;
+                (dx.abs().max(dy.abs()), dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+        }
+        TileOrder::Hilbert => {
+            let n = nx.max(ny).next_power_of_two();
+            grid.sort_by_key(|&(tx, ty)| hilbert_index(n, tx, ty));
+        }
+    }
+
+    grid.into_iter()
+        .map(|(tx, ty)| Tile {
+            x0: tx * size,
+            y0: ty * size,
+            x1: ((tx + 1) * size).min(width),
+            y1: ((ty + 1) * size).min(height),
+        })
+        .collect()
+}
+
+// Distance of (x, y) along the Hilbert curve filling an n x n grid, n a power of two.
+fn hilbert_index(n: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        // rotate the quadrant so the sub-curve starts and ends in the right corners
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
@@ -0,0 +1,116 @@
+use super::color::Color;
+
+// Display transform for the LDR outputs: exposure scale, then a tone curve that maps
+// unbounded radiance into [0, 1]. The sRGB encoding itself happens in `color_to_rgb8`.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMap {
+    // no curve, values above 1 are clipped
+    Clamp,
+    // x / (1 + x)
+    Reinhard,
+    // Reinhard that maps `white_point` to 1 instead of infinity
+    ReinhardExtended,
+    // John Hable's Uncharted 2 filmic curve
+    Hable,
+    // Stephen Hill's fit of the ACES RRT + sRGB ODT
+    Aces,
+}
+
+impl ToneMap {
+    pub const NAMES: &'static str = "clamp, reinhard, reinhard_extended, hable, aces";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clamp" | "none" => Some(ToneMap::Clamp),
+            "reinhard" => Some(ToneMap::Reinhard),
+            "reinhard_extended" => Some(ToneMap::ReinhardExtended),
+            "hable" | "uncharted2" => Some(ToneMap::Hable),
+            "aces" => Some(ToneMap::Aces),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ToneMapping {
+    pub operator: ToneMap,
+    pub exposure: f64,    // in stops, each one doubles the brightness
+    pub white_point: f64, // smallest radiance that maps to white (extended Reinhard)
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            operator: ToneMap::Clamp,
+            exposure: 0.0,
+            white_point: 4.0,
+        }
+    }
+}
+
+impl ToneMapping {
+    // Maps linear scene radiance to linear display values in [0, 1].
+    pub fn apply(&self, color: &Color) -> Color {
+        let c = 2_f64.powf(self.exposure) * color;
+        let c = Color::new(c.x().max(0.0), c.y().max(0.0), c.z().max(0.0));
+        let mapped = match self.operator {
+            ToneMap::Clamp => c,
+            ToneMap::Reinhard => map(&c, |x| x / (1.0 + x)),
+            ToneMap::ReinhardExtended => {
+                let w2 = self.white_point * self.white_point;
+                map(&c, |x| x * (1.0 + x / w2) / (1.0 + x))
+            }
+            ToneMap::Hable => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                const WHITE: f64 = 11.2;
+                let scale = 1.0 / hable_partial(WHITE);
+                map(&c, |x| hable_partial(EXPOSURE_BIAS * x) * scale)
+            }
+            ToneMap::Aces => aces_fitted(&c),
+        };
+        map(&mapped, |x| x.clamp(0.0, 1.0))
+    }
+}
+
+fn map(c: &Color, f: impl Fn(f64) -> f64) -> Color {
+    Color::new(f(c.x()), f(c.y()), f(c.z()))
+}
+
+fn hable_partial(x: f64) -> f64 {
+    const A: f64 = 0.15; // shoulder strength
+    const B: f64 = 0.50; // linear strength
+    const C: f64 = 0.10; // linear angle
+    const D: f64 = 0.20; // toe strength
+    const E: f64 = 0.02; // toe numerator
+    const F: f64 = 0.30; // toe denominator
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+fn aces_fitted(c: &Color) -> Color {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    const INPUT: [[f64; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    const OUTPUT: [[f64; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let mul = |m: &[[f64; 3]; 3], v: &Color| {
+        Color::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    };
+    let v = mul(&INPUT, c);
+    // RRT and ODT fit
+    let v = map(&v, |x| {
+        (x * (x + 0.0245786) - 0.000090537) / (x * (0.983729 * x + 0.4329510) + 0.238081)
+    });
+    mul(&OUTPUT, &v)
+}
@@ -1,6 +1,8 @@
 use std::fmt;
 use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub};
 
+use super::sampler::Sampler;
+
 #[derive(Clone, Copy)]
 pub struct Vec3 {
     pub e: [f64; 3],
@@ -46,26 +48,26 @@ impl Vec3 {
         )
     }
 
-    pub fn random(min: f64, max: f64) -> Vec3 {
+    pub fn random(sampler: &mut dyn Sampler, min: f64, max: f64) -> Vec3 {
         Vec3::new(
-            min + (max - min) * rand::random::<f64>(),
-            min + (max - min) * rand::random::<f64>(),
-            min + (max - min) * rand::random::<f64>(),
+            sampler.range(min, max),
+            sampler.range(min, max),
+            sampler.range(min, max),
         )
     }
 
-    pub fn random_unit_vector() -> Vec3 {
-        loop {
-            let p = Vec3::random(-1.0, 1.0);
-            let lensp = p.length_squared();
-            if lensp <= 1. && lensp >= 1e-16 {
-                return p / lensp.sqrt();
-            }
-        }
+    // Direct mappings rather than rejection loops below, so each call uses a fixed number
+    // of sampler dimensions.
+    pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
+        let z = 1.0 - 2.0 * r1;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * r2;
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
     }
 
-    pub fn random_on_hemisphere(normal: &Vec3) -> Vec3 {
-        let on_unit_sphere = Vec3::random_unit_vector();
+    pub fn random_on_hemisphere(sampler: &mut dyn Sampler, normal: &Vec3) -> Vec3 {
+        let on_unit_sphere = Vec3::random_unit_vector(sampler);
         if on_unit_sphere.dot(normal) > 0.0 {
             on_unit_sphere
         } else {
@@ -73,17 +75,22 @@ impl Vec3 {
         }
     }
 
-    pub fn random_in_unit_disk() -> Vec3 {
-        loop {
-            let p = Vec3::new(
-                rand::random::<f64>() * 2.0 - 1.0,
-                rand::random::<f64>() * 2.0 - 1.0,
-                0.0,
-            );
-            if p.length_squared() <= 1.0 {
-                return p;
-            }
-        }
+    // Direction around +z with density cos(theta) / pi.
+    pub fn random_cosine_direction(sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
+
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+        let z = (1.0 - r2).sqrt();
+        Vec3::new(x, y, z)
+    }
+
+    pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vec3 {
+        let (r1, r2) = sampler.get_2d();
+        let r = r1.sqrt();
+        let phi = 2.0 * std::f64::consts::PI * r2;
+        Vec3::new(r * phi.cos(), r * phi.sin(), 0.0)
     }
 
     pub fn near_zero(&self) -> bool {